@@ -7,12 +7,36 @@ mod circuits {
     // Point basis: 1 USDC = 1000 shares
     const SHARES_PER_USDC: u64 = 1000;
 
+    // Maximum number of outcomes in a categorical market
+    const MAX_OUTCOMES: usize = 8;
+
+    // Slots in a market's limit order book per private window
+    const MAX_LIMIT_ORDERS: usize = 8;
+
+    // Position size buckets by net USDC: < 10, < 100, < 1000, >= 1000
+    const POSITION_SIZE_BUCKETS: usize = 4;
+
     #[derive(Clone)]
     pub struct MarketState {
         pub yes_pool: u64,  
         pub no_pool: u64,  
         pub k: u64,         // Constant product
         pub total_trades: u64,
+        pub window_trades: u64,       // Trades since the current private window opened
+        pub window_volume_usdc: u64,  // USDC traded since the current private window opened
+        pub window_yes_volume_usdc: u64,  // Part of window_volume_usdc spent on YES
+    }
+
+    // Decision-ready sponsor view: implied price plus activity since the private window opened
+    #[derive(Clone)]
+    pub struct MarketSummary {
+        pub yes_pool: u64,
+        pub no_pool: u64,
+        pub yes_price: u64,  // Implied YES probability in thousandths (0-1000)
+        pub window_trades: u64,
+        pub window_yes_volume_usdc: u64,
+        pub window_no_volume_usdc: u64,
+        pub window_traders: u64,
     }
 
     #[derive(Clone)]
@@ -21,6 +45,7 @@ mod circuits {
         pub no_tokens: u64,   // Active no shares
         pub yes_tokens_closed: u64,  // Closed yes shares (in escrow)
         pub no_tokens_closed: u64,   // Closed no shares (in escrow)
+        pub net_usdc: u64,  // USDC put in by the trader, refunded if the market is invalid
     }
 
     #[derive(Clone)]
@@ -35,6 +60,78 @@ mod circuits {
         pub close_no_tokens: u64,   
     }
 
+    // Aggregate of many positions, the only position data a k-anonymous sponsor view reveals
+    #[derive(Clone)]
+    pub struct PositionHistogram {
+        pub staked_positions: u64,  // Positions with a non-zero stake; only these are counted below
+        pub size_buckets: [u64; POSITION_SIZE_BUCKETS],  // Positions per net USDC bucket
+        pub yes_holders: u64,  // Positions holding any active YES shares
+        pub no_holders: u64,   // Positions holding any active NO shares
+    }
+
+    #[derive(Clone)]
+    pub struct LimitOrderInput {
+        pub is_buy: bool,       // Buy to open, or sell to close
+        pub is_yes: bool,
+        pub size: u64,          // Shares
+        pub limit_price: u64,   // In thousandths (0-1000)
+    }
+
+    // One slot per order, stored column-wise; an empty slot has size 0
+    #[derive(Clone)]
+    pub struct LimitOrderBook {
+        pub is_buy: [bool; MAX_LIMIT_ORDERS],
+        pub is_yes: [bool; MAX_LIMIT_ORDERS],
+        pub size: [u64; MAX_LIMIT_ORDERS],
+        pub limit_price: [u64; MAX_LIMIT_ORDERS],
+    }
+
+    // Executed shares and USDC per order slot, zero if the order did not cross
+    #[derive(Clone)]
+    pub struct LimitFills {
+        pub shares: [u64; MAX_LIMIT_ORDERS],
+        pub usdc: [u64; MAX_LIMIT_ORDERS],
+    }
+
+    // Aggregate USDC demand of one private-window order batch
+    #[derive(Clone)]
+    pub struct BatchTotals {
+        pub yes_usdc: u64,
+        pub no_usdc: u64,
+    }
+
+    // Uniform prices a batch cleared at, in thousandths
+    #[derive(Clone)]
+    pub struct ClearingPrices {
+        pub yes_price: u64,
+        pub no_price: u64,
+    }
+
+    // Content hash of a signal provider's off-chain research, split into two halves
+    #[derive(Clone)]
+    pub struct ResearchNote {
+        pub hash_hi: u128,
+        pub hash_lo: u128,
+    }
+
+    #[derive(Clone)]
+    pub struct CategoricalMarketState {
+        pub pools: [u64; MAX_OUTCOMES],  // One pool per outcome, unused slots stay at 0
+        pub total_trades: u64,
+    }
+
+    #[derive(Clone)]
+    pub struct CategoricalPosition {
+        pub tokens: [u64; MAX_OUTCOMES],  // Active shares per outcome
+        pub net_usdc: u64,  // USDC put in by the trader, refunded if the market is invalid
+    }
+
+    #[derive(Clone)]
+    pub struct CategoricalTradeInput {
+        pub usdc_amount: u64,
+        pub outcome_index: u8,
+    }
+
     #[instruction]
     pub fn initialize_market(
         mxe: Mxe,
@@ -49,8 +146,11 @@ mod circuits {
         let state = MarketState {
             yes_pool: shares_per_side,
             no_pool: shares_per_side,
-            k: k, 
+            k, 
             total_trades: 0,
+            window_trades: 0,
+            window_volume_usdc: 0,
+            window_yes_volume_usdc: 0,
         };
         mxe.from_arcis(state)
     }
@@ -62,81 +162,18 @@ mod circuits {
             no_tokens: 0,
             yes_tokens_closed: 0,
             no_tokens_closed: 0,
+            net_usdc: 0,
         };
         mxe.from_arcis(position)
     }
 
-    #[instruction]
-    pub fn process_private_trade(
-        market_nonce: u128,
-        market_ctxt: Enc<Mxe, MarketState>,
-        trade_ctxt: Enc<Shared, TradeInput>,
-    ) -> Enc<Mxe, MarketState> {
-        let mut state = market_ctxt.to_arcis();
-        let trade = trade_ctxt.to_arcis();
-
-        // Convert USDC to shares
-        let shares_input = trade.usdc_amount * SHARES_PER_USDC;
-
-        // k was stored as scaled down by 1000000 (1000^2)
-        let k_scaled = state.k * 1000000;
-
-        if trade.is_buy_yes {
-            // Buying YES: user adds shares_input to no_pool, receives shares from yes_pool
-            // Formula: (yes_pool - shares_out) * (no_pool + shares_input) = k_scaled
-            // shares_out = yes_pool - k_scaled / (no_pool + shares_input)
-            
-            let new_no_pool = state.no_pool + shares_input;
-            let new_yes_pool = k_scaled / new_no_pool;
-            let shares_received = state.yes_pool - new_yes_pool;
-            
-            state.yes_pool = new_yes_pool;
-            state.no_pool = new_no_pool;
-            
-        } else {
-            // Buying NO: user adds shares_input to yes_pool, receives shares from no_pool
-            // Formula: (yes_pool + shares_input) * (no_pool - shares_out) = k_scaled
-            // shares_out = no_pool - k_scaled / (yes_pool + shares_input)
-            
-            let new_yes_pool = state.yes_pool + shares_input;
-            let new_no_pool = k_scaled / new_yes_pool;
-            let shares_received = state.no_pool - new_no_pool;
-            
-            state.yes_pool = new_yes_pool;
-            state.no_pool = new_no_pool;
-        }
-
-        state.total_trades += 1;
-        market_ctxt.owner.from_arcis(state)
-    }
-
-    #[instruction]
-    pub fn update_user_position(
-        position_nonce: u128,
-        position_ctxt: Enc<Mxe, UserPosition>,
-        trade_ctxt: Enc<Shared, TradeInput>,
-    ) -> Enc<Mxe, UserPosition> {
-        let mut position = position_ctxt.to_arcis();
-        let trade = trade_ctxt.to_arcis();
-
-        // Convert USDC to shares
-        let shares = trade.usdc_amount * SHARES_PER_USDC;
-
-        if trade.is_buy_yes {
-            position.yes_tokens += shares;
-        } else {
-            position.no_tokens += shares;
-        }
-
-        position_ctxt.owner.from_arcis(position)
-    }
-
     #[instruction]
     pub fn close_position(
         position_nonce: u128,
         position_ctxt: Enc<Mxe, UserPosition>,
         close_ctxt: Enc<Shared, CloseInput>,
-    ) -> Enc<Mxe, UserPosition> {
+        state_version: u64,
+    ) -> (Enc<Mxe, UserPosition>, u64) {
         let mut position = position_ctxt.to_arcis();
         let close = close_ctxt.to_arcis();
 
@@ -150,11 +187,14 @@ mod circuits {
             position.no_tokens_closed += close.close_no_tokens;
         }
 
-        position_ctxt.owner.from_arcis(position)
+        (position_ctxt.owner.from_arcis(position), state_version)
     }
 
     #[instruction]
-    pub fn reveal_market_state(market_ctxt: Enc<Mxe, MarketState>) -> (u64, u64, u64, u64, u64) {
+    pub fn reveal_market_state(
+        market_ctxt: Enc<Mxe, MarketState>,
+        state_version: u64,
+    ) -> (u64, u64, u64, u64, u64, u64) {
         let state = market_ctxt.to_arcis();
         
         // Calculate current prices:
@@ -173,28 +213,44 @@ mod circuits {
             state.no_pool.reveal(),
             yes_price.reveal(),
             no_price.reveal(),
-            state.total_trades.reveal()
+            state.total_trades.reveal(),
+            state_version
         )
     }
 
     #[instruction]
-    pub fn reveal_user_position(position_ctxt: Enc<Mxe, UserPosition>) -> (u64, u64, u64, u64) {
+    pub fn reveal_user_position(position_ctxt: Enc<Mxe, UserPosition>) -> (u64, u64, u64, u64, u64) {
         let position = position_ctxt.to_arcis();
         (
             position.yes_tokens.reveal(),
             position.no_tokens.reveal(),
             position.yes_tokens_closed.reveal(),
-            position.no_tokens_closed.reveal()
+            position.no_tokens_closed.reveal(),
+            position.net_usdc.reveal()
         )
     }
 
+    // Refund owed when a market resolves invalid - only the USDC total is revealed, not the holdings
+    #[instruction]
+    pub fn reveal_refund(position_ctxt: Enc<Mxe, UserPosition>) -> u64 {
+        let position = position_ctxt.to_arcis();
+        position.net_usdc.reveal()
+    }
+
+    #[instruction]
+    pub fn reveal_categorical_refund(position_ctxt: Enc<Mxe, CategoricalPosition>) -> u64 {
+        let position = position_ctxt.to_arcis();
+        position.net_usdc.reveal()
+    }
+
     #[instruction]
     pub fn hide_market_state(
         mxe: Mxe,
         yes_pool: u64,
         no_pool: u64,
-        total_trades: u64
-    ) -> Enc<Mxe, MarketState> {
+        total_trades: u64,
+        state_version: u64,
+    ) -> (Enc<Mxe, MarketState>, u64) {
         let k = (yes_pool / 1000) * (no_pool / 1000);
         
         let state = MarketState {
@@ -202,8 +258,12 @@ mod circuits {
             no_pool,
             k,
             total_trades,
+            // A new private window starts with no activity
+            window_trades: 0,
+            window_volume_usdc: 0,
+            window_yes_volume_usdc: 0,
         };
-        mxe.from_arcis(state)
+        (mxe.from_arcis(state), state_version)
     }
 
     // Sponsor view of market state - re-encrypt for sponsor's viewing
@@ -216,13 +276,585 @@ mod circuits {
         sponsor_ctx.from_arcis(state)
     }
 
-    // Sponsor view of user position - re-encrypt for sponsor's viewing
+    // Sponsor view with the price math done inside MPC. window_traders is counted on-chain.
+    #[instruction]
+    pub fn view_market_summary(
+        market_ctxt: Enc<Mxe, MarketState>,
+        sponsor_ctx: Shared,
+        window_traders: u64,
+    ) -> Enc<Shared, MarketSummary> {
+        let state = market_ctxt.to_arcis();
+
+        let total_pool = state.yes_pool + state.no_pool;
+        let safe_total_pool = if total_pool > 0 { total_pool } else { 1 };
+        let yes_price = ((state.no_pool as u128) * 1000 / (safe_total_pool as u128)) as u64;
+
+        let summary = MarketSummary {
+            yes_pool: state.yes_pool,
+            no_pool: state.no_pool,
+            yes_price,
+            window_trades: state.window_trades,
+            window_yes_volume_usdc: state.window_yes_volume_usdc,
+            window_no_volume_usdc: state.window_volume_usdc - state.window_yes_volume_usdc,
+            window_traders,
+        };
+        sponsor_ctx.from_arcis(summary)
+    }
+
+    #[instruction]
+    pub fn initialize_position_histogram(mxe: Mxe) -> Enc<Mxe, PositionHistogram> {
+        let histogram = PositionHistogram {
+            staked_positions: 0,
+            size_buckets: [0; POSITION_SIZE_BUCKETS],
+            yes_holders: 0,
+            no_holders: 0,
+        };
+        mxe.from_arcis(histogram)
+    }
+
+    // Fold one position into the histogram without revealing anything about it.
+    // Empty positions are left out so they can't pad the histogram up to the view threshold.
+    #[instruction]
+    pub fn add_position_to_histogram(
+        position_ctxt: Enc<Mxe, UserPosition>,
+        histogram_ctxt: Enc<Mxe, PositionHistogram>,
+    ) -> Enc<Mxe, PositionHistogram> {
+        let position = position_ctxt.to_arcis();
+        let mut histogram = histogram_ctxt.to_arcis();
+
+        if position.net_usdc > 0 {
+            histogram.staked_positions += 1;
+            if position.net_usdc < 10 {
+                histogram.size_buckets[0] += 1;
+            } else if position.net_usdc < 100 {
+                histogram.size_buckets[1] += 1;
+            } else if position.net_usdc < 1000 {
+                histogram.size_buckets[2] += 1;
+            } else {
+                histogram.size_buckets[3] += 1;
+            }
+            if position.yes_tokens > 0 {
+                histogram.yes_holders += 1;
+            }
+            if position.no_tokens > 0 {
+                histogram.no_holders += 1;
+            }
+        }
+
+        histogram_ctxt.owner.from_arcis(histogram)
+    }
+
+    // Below `min_positions` staked positions the sponsor gets an all-zero histogram
+    #[instruction]
+    pub fn view_position_histogram(
+        histogram_ctxt: Enc<Mxe, PositionHistogram>,
+        min_positions: u64,
+        sponsor_ctx: Shared,
+    ) -> Enc<Shared, PositionHistogram> {
+        let histogram = histogram_ctxt.to_arcis();
+        let empty = PositionHistogram {
+            staked_positions: 0,
+            size_buckets: [0u64; POSITION_SIZE_BUCKETS],
+            yes_holders: 0,
+            no_holders: 0,
+        };
+        let visible = if histogram.staked_positions >= min_positions {
+            histogram
+        } else {
+            empty
+        };
+        sponsor_ctx.from_arcis(visible)
+    }
+
+    // Sponsor view of user position - re-encrypt for sponsor's viewing.
+    // Gated on the histogram's staked count like view_position_histogram, so empty
+    // positions can't unlock it; below `min_positions` the sponsor gets an all-zero position.
     #[instruction]
     pub fn view_user_position(
         position_ctxt: Enc<Mxe, UserPosition>,
+        histogram_ctxt: Enc<Mxe, PositionHistogram>,
+        min_positions: u64,
         sponsor_ctx: Shared,
     ) -> Enc<Shared, UserPosition> {
         let position = position_ctxt.to_arcis();
-        sponsor_ctx.from_arcis(position)
+        let histogram = histogram_ctxt.to_arcis();
+        let empty = UserPosition {
+            yes_tokens: 0,
+            no_tokens: 0,
+            yes_tokens_closed: 0,
+            no_tokens_closed: 0,
+            net_usdc: 0,
+        };
+        let visible = if histogram.staked_positions >= min_positions {
+            position
+        } else {
+            empty
+        };
+        sponsor_ctx.from_arcis(visible)
+    }
+
+    // Re-encrypt a position to its owner so the trader can read their own fills
+    #[instruction]
+    pub fn view_my_position(
+        position_ctxt: Enc<Mxe, UserPosition>,
+        user_ctx: Shared,
+    ) -> Enc<Shared, UserPosition> {
+        let position = position_ctxt.to_arcis();
+        user_ctx.from_arcis(position)
+    }
+
+    #[instruction]
+    pub fn initialize_categorical_market(
+        mxe: Mxe,
+        initial_liquidity_usdc: u64,
+        num_outcomes: u8,
+    ) -> Enc<Mxe, CategoricalMarketState> {
+        // Every outcome starts with an equal pool, i.e. price = 1 / num_outcomes
+        let total_shares = initial_liquidity_usdc * SHARES_PER_USDC;
+        let shares_per_outcome = total_shares / (num_outcomes as u64);
+
+        let mut pools = [0u64; MAX_OUTCOMES];
+        for (i, pool) in pools.iter_mut().enumerate() {
+            if (i as u8) < num_outcomes {
+                *pool = shares_per_outcome;
+            }
+        }
+
+        let state = CategoricalMarketState {
+            pools,
+            total_trades: 0,
+        };
+        mxe.from_arcis(state)
+    }
+
+    #[instruction]
+    pub fn initialize_categorical_position(mxe: Mxe) -> Enc<Mxe, CategoricalPosition> {
+        let position = CategoricalPosition {
+            tokens: [0u64; MAX_OUTCOMES],
+            net_usdc: 0,
+        };
+        mxe.from_arcis(position)
+    }
+
+    // Trades naming an outcome at or past num_outcomes are ignored for the pools and the
+    // position alike, so the two never disagree
+    #[instruction]
+    pub fn process_categorical_trade(
+        market_ctxt: Enc<Mxe, CategoricalMarketState>,
+        position_ctxt: Enc<Mxe, CategoricalPosition>,
+        trade_ctxt: Enc<Shared, CategoricalTradeInput>,
+        num_outcomes: u8,
+        state_version: u64,
+        position_version: u64,
+    ) -> (Enc<Mxe, CategoricalMarketState>, Enc<Mxe, CategoricalPosition>, u64, u64) {
+        let mut state = market_ctxt.to_arcis();
+        let mut position = position_ctxt.to_arcis();
+        let trade = trade_ctxt.to_arcis();
+
+        let shares_input = trade.usdc_amount * SHARES_PER_USDC;
+
+        // Split the pools into the chosen outcome and the rest of the market
+        let mut target_pool = 0u64;
+        let mut total_pool = 0u64;
+        for i in 0..MAX_OUTCOMES {
+            total_pool += state.pools[i];
+            if trade.outcome_index == i as u8 {
+                target_pool = state.pools[i];
+            }
+        }
+        let rest_pool = total_pool - target_pool;
+
+        // Same constant product as the binary market, with "rest" playing the opposite side:
+        // (target_pool - shares_out) * (rest_pool + shares_input) = k_scaled
+        // Both branches are evaluated under MPC, so keep the divisions away from zero
+        let k_scaled = (target_pool / 1000) * (rest_pool / 1000) * 1000000;
+        let new_rest_pool = rest_pool + shares_input;
+        let safe_new_rest_pool = if new_rest_pool > 0 { new_rest_pool } else { 1 };
+        let safe_rest_pool = if rest_pool > 0 { rest_pool } else { 1 };
+        let new_target_pool = k_scaled / safe_new_rest_pool;
+
+        if trade.outcome_index < num_outcomes {
+            // Spread the added shares over the other outcomes in proportion to their pools
+            for i in 0..MAX_OUTCOMES {
+                if trade.outcome_index == i as u8 {
+                    state.pools[i] = new_target_pool;
+                } else {
+                    let added = (shares_input as u128 * state.pools[i] as u128) / safe_rest_pool as u128;
+                    state.pools[i] += added as u64;
+                }
+            }
+
+            state.total_trades += 1;
+
+            // The trader is credited in the same computation, so pools and position can't diverge
+            for i in 0..MAX_OUTCOMES {
+                if trade.outcome_index == i as u8 {
+                    position.tokens[i] += shares_input;
+                }
+            }
+            position.net_usdc += trade.usdc_amount;
+        }
+
+        // Both versions are echoed back so the callback can reject output computed from stale state
+        (
+            market_ctxt.owner.from_arcis(state),
+            position_ctxt.owner.from_arcis(position),
+            state_version,
+            position_version,
+        )
+    }
+
+    // Take ownership of a trader-encrypted research hash so it can later be shown to the sponsor
+    #[instruction]
+    pub fn commit_research(
+        note_ctxt: Enc<Shared, ResearchNote>,
+        mxe: Mxe,
+    ) -> Enc<Mxe, ResearchNote> {
+        let note = note_ctxt.to_arcis();
+        mxe.from_arcis(note)
+    }
+
+    // Sponsor view of a research commitment - re-encrypt for sponsor's viewing
+    #[instruction]
+    pub fn view_research(
+        note_ctxt: Enc<Mxe, ResearchNote>,
+        sponsor_ctx: Shared,
+    ) -> Enc<Shared, ResearchNote> {
+        let note = note_ctxt.to_arcis();
+        sponsor_ctx.from_arcis(note)
+    }
+
+    #[instruction]
+    pub fn reveal_research(note_ctxt: Enc<Mxe, ResearchNote>) -> (u128, u128) {
+        let note = note_ctxt.to_arcis();
+        (note.hash_hi.reveal(), note.hash_lo.reveal())
+    }
+
+    // Score a position in a resolved binary market: returns (staked_usdc, payout_usdc,
+    // entry_price, brier), prices and score in thousandths
+    #[instruction]
+    pub fn score_position(
+        position_ctxt: Enc<Mxe, UserPosition>,
+        resolved_yes: bool,
+    ) -> (u64, u64, u64, u64) {
+        let position = position_ctxt.to_arcis();
+        let total_tokens = position.yes_tokens + position.no_tokens;
+        let winning_tokens = if resolved_yes { position.yes_tokens } else { position.no_tokens };
+        let denom = if total_tokens > 0 { total_tokens } else { 1 };
+
+        let payout_usdc = winning_tokens / SHARES_PER_USDC;
+
+        // Average price paid per share, where a winning share pays 1 / SHARES_PER_USDC USDC
+        let entry_price = ((position.net_usdc as u128) * (SHARES_PER_USDC as u128) * 1000
+            / (denom as u128)) as u64;
+
+        // Brier-style: the trader's YES/NO split is their forecast of the realized outcome
+        let forecast = ((winning_tokens as u128) * 1000 / (denom as u128)) as u64;
+        let miss = 1000 - forecast;
+        let brier = miss * miss / 1000;
+
+        (
+            position.net_usdc.reveal(),
+            payout_usdc.reveal(),
+            entry_price.reveal(),
+            brier.reveal(),
+        )
+    }
+
+    // Coarse activity level of the current private window (0 = low, 1 = medium, 2 = high).
+    // Volume is measured against the market's initial liquidity; prices stay hidden.
+    #[instruction]
+    pub fn reveal_attention_hint(
+        market_ctxt: Enc<Mxe, MarketState>,
+        initial_liquidity_usdc: u64,
+    ) -> u8 {
+        let state = market_ctxt.to_arcis();
+
+        let volume_permille = if initial_liquidity_usdc > 0 {
+            ((state.window_volume_usdc as u128) * 1000 / (initial_liquidity_usdc as u128)) as u64
+        } else {
+            0
+        };
+
+        let level: u8 = if volume_permille >= 250 || state.window_trades >= 50 {
+            2
+        } else if volume_permille >= 50 || state.window_trades >= 5 {
+            1
+        } else {
+            0
+        };
+        level.reveal()
+    }
+
+    #[instruction]
+    pub fn initialize_order_batch(mxe: Mxe) -> Enc<Mxe, BatchTotals> {
+        let totals = BatchTotals {
+            yes_usdc: 0,
+            no_usdc: 0,
+        };
+        mxe.from_arcis(totals)
+    }
+
+    // Store a trader's order for the batch and add it to the batch totals. Nothing touches
+    // the pools until the batch clears, so submission order does not matter.
+    #[instruction]
+    pub fn submit_batch_order(
+        order_ctxt: Enc<Shared, TradeInput>,
+        batch_ctxt: Enc<Mxe, BatchTotals>,
+    ) -> (Enc<Mxe, TradeInput>, Enc<Mxe, BatchTotals>) {
+        let order = order_ctxt.to_arcis();
+        let mut totals = batch_ctxt.to_arcis();
+
+        if order.is_buy_yes {
+            totals.yes_usdc += order.usdc_amount;
+        } else {
+            totals.no_usdc += order.usdc_amount;
+        }
+
+        // The owner is consumed by encryption, so the order gets a fresh MXE owner and nonce
+        (Mxe::get().from_arcis(order), batch_ctxt.owner.from_arcis(totals))
+    }
+
+    // Clear a batch at one uniform price: opposite orders net against each other and only
+    // the imbalance trades against the pool. Every order then fills at the post-batch price.
+    #[instruction]
+    pub fn clear_order_batch(
+        market_ctxt: Enc<Mxe, MarketState>,
+        batch_ctxt: Enc<Mxe, BatchTotals>,
+        order_count: u64,
+        state_version: u64,
+    ) -> (Enc<Mxe, MarketState>, Enc<Mxe, ClearingPrices>, u64) {
+        let mut state = market_ctxt.to_arcis();
+        let totals = batch_ctxt.to_arcis();
+
+        // k was stored as scaled down by 1000000 (1000^2)
+        let k_scaled = state.k * 1000000;
+
+        if totals.yes_usdc >= totals.no_usdc {
+            let net_shares = (totals.yes_usdc - totals.no_usdc) * SHARES_PER_USDC;
+            let new_no_pool = state.no_pool + net_shares;
+            state.yes_pool = k_scaled / new_no_pool;
+            state.no_pool = new_no_pool;
+        } else {
+            let net_shares = (totals.no_usdc - totals.yes_usdc) * SHARES_PER_USDC;
+            let new_yes_pool = state.yes_pool + net_shares;
+            state.no_pool = k_scaled / new_yes_pool;
+            state.yes_pool = new_yes_pool;
+        }
+
+        // Same price formula as reveal_market_state, kept off the extremes so fills stay finite
+        let total_pool = state.yes_pool + state.no_pool;
+        let raw_yes_price = ((state.no_pool as u128) * 1000 / (total_pool as u128)) as u64;
+        // `clamp` isn't available in circuits
+        let floored_yes_price = raw_yes_price.max(1);
+        let yes_price = floored_yes_price.min(999);
+        let prices = ClearingPrices {
+            yes_price,
+            no_price: 1000 - yes_price,
+        };
+
+        state.total_trades += order_count;
+        state.window_trades += order_count;
+        state.window_volume_usdc += totals.yes_usdc + totals.no_usdc;
+        state.window_yes_volume_usdc += totals.yes_usdc;
+
+        (
+            market_ctxt.owner.from_arcis(state),
+            batch_ctxt.owner.from_arcis(prices),
+            state_version,
+        )
+    }
+
+    // Credit a trader with their batch fill at the uniform clearing price. A winning share
+    // pays 1 / SHARES_PER_USDC USDC, so shares = usdc * SHARES_PER_USDC * 1000 / price.
+    #[instruction]
+    pub fn fill_batch_order(
+        position_ctxt: Enc<Mxe, UserPosition>,
+        order_ctxt: Enc<Mxe, TradeInput>,
+        prices_ctxt: Enc<Mxe, ClearingPrices>,
+        state_version: u64,
+    ) -> (Enc<Mxe, UserPosition>, u64) {
+        let mut position = position_ctxt.to_arcis();
+        let order = order_ctxt.to_arcis();
+        let prices = prices_ctxt.to_arcis();
+
+        let price = if order.is_buy_yes { prices.yes_price } else { prices.no_price };
+        let shares = ((order.usdc_amount as u128) * (SHARES_PER_USDC as u128) * 1000
+            / (price as u128)) as u64;
+
+        if order.is_buy_yes {
+            position.yes_tokens += shares;
+        } else {
+            position.no_tokens += shares;
+        }
+        position.net_usdc += order.usdc_amount;
+
+        (position_ctxt.owner.from_arcis(position), state_version)
+    }
+
+    #[instruction]
+    pub fn initialize_limit_order_book(mxe: Mxe) -> Enc<Mxe, LimitOrderBook> {
+        let book = LimitOrderBook {
+            is_buy: [false; MAX_LIMIT_ORDERS],
+            is_yes: [false; MAX_LIMIT_ORDERS],
+            size: [0; MAX_LIMIT_ORDERS],
+            limit_price: [0; MAX_LIMIT_ORDERS],
+        };
+        mxe.from_arcis(book)
+    }
+
+    // Write a trader's order into its reserved slot. Sell-to-close orders are capped
+    // at the shares the trader currently holds.
+    #[instruction]
+    pub fn place_limit_order(
+        order_ctxt: Enc<Shared, LimitOrderInput>,
+        position_ctxt: Enc<Mxe, UserPosition>,
+        book_ctxt: Enc<Mxe, LimitOrderBook>,
+        slot: u8,
+    ) -> Enc<Mxe, LimitOrderBook> {
+        let order = order_ctxt.to_arcis();
+        let position = position_ctxt.to_arcis();
+        let mut book = book_ctxt.to_arcis();
+
+        let held = if order.is_yes { position.yes_tokens } else { position.no_tokens };
+        let capped = if order.size < held { order.size } else { held };
+        let size = if order.is_buy { order.size } else { capped };
+
+        for i in 0..MAX_LIMIT_ORDERS {
+            if (i as u8) == slot {
+                book.is_buy[i] = order.is_buy;
+                book.is_yes[i] = order.is_yes;
+                book.size[i] = size;
+                book.limit_price[i] = order.limit_price;
+            }
+        }
+
+        book_ctxt.owner.from_arcis(book)
+    }
+
+    // Walk the book in slot order and execute every order whose limit crosses the
+    // current pool price, moving the pool as each one fills
+    #[instruction]
+    pub fn match_limit_orders(
+        market_ctxt: Enc<Mxe, MarketState>,
+        book_ctxt: Enc<Mxe, LimitOrderBook>,
+        state_version: u64,
+    ) -> (Enc<Mxe, MarketState>, Enc<Mxe, LimitFills>, u64) {
+        let mut state = market_ctxt.to_arcis();
+        let book = book_ctxt.to_arcis();
+
+        // k was stored as scaled down by 1000000 (1000^2)
+        let k_scaled = (state.k as u128) * 1000000;
+
+        let mut fills = LimitFills {
+            shares: [0; MAX_LIMIT_ORDERS],
+            usdc: [0; MAX_LIMIT_ORDERS],
+        };
+
+        for i in 0..MAX_LIMIT_ORDERS {
+            let size = book.size[i];
+            let is_buy = book.is_buy[i];
+            let is_yes = book.is_yes[i];
+
+            let total_pool = state.yes_pool + state.no_pool;
+            let yes_price = ((state.no_pool as u128) * 1000 / (total_pool as u128)) as u64;
+            let side_price = if is_yes { yes_price } else { 1000 - yes_price };
+            let crosses = if is_buy {
+                side_price <= book.limit_price[i]
+            } else {
+                side_price >= book.limit_price[i]
+            };
+
+            // Buying takes shares out of the traded side's pool, selling returns them
+            let side_pool = if is_yes { state.yes_pool } else { state.no_pool };
+            let other_pool = if is_yes { state.no_pool } else { state.yes_pool };
+            let executable = size > 0 && crosses && (!is_buy || size < side_pool);
+
+            // Both branches are evaluated under MPC, so keep the unexecuted one from underflowing
+            let buy_size = if size < side_pool { size } else { 0 };
+            let new_side_pool = if is_buy { side_pool - buy_size } else { side_pool + size };
+            let safe_side_pool = if new_side_pool > 0 { new_side_pool } else { 1 };
+            let new_other_pool = (k_scaled / (safe_side_pool as u128)) as u64;
+            // `saturating_sub` isn't available in circuits
+            let other_delta = if is_buy {
+                new_other_pool.max(other_pool) - other_pool
+            } else {
+                other_pool.max(new_other_pool) - new_other_pool
+            };
+
+            if executable {
+                fills.shares[i] = size;
+                fills.usdc[i] = other_delta / SHARES_PER_USDC;
+                if is_yes {
+                    state.yes_pool = new_side_pool;
+                    state.no_pool = new_other_pool;
+                } else {
+                    state.no_pool = new_side_pool;
+                    state.yes_pool = new_other_pool;
+                }
+                state.total_trades += 1;
+                state.window_trades += 1;
+                state.window_volume_usdc += fills.usdc[i];
+                if is_yes {
+                    state.window_yes_volume_usdc += fills.usdc[i];
+                }
+            }
+        }
+
+        (
+            market_ctxt.owner.from_arcis(state),
+            book_ctxt.owner.from_arcis(fills),
+            state_version,
+        )
+    }
+
+    // Apply one slot's fill to the order owner's position
+    #[instruction]
+    pub fn apply_limit_fill(
+        position_ctxt: Enc<Mxe, UserPosition>,
+        book_ctxt: Enc<Mxe, LimitOrderBook>,
+        fills_ctxt: Enc<Mxe, LimitFills>,
+        slot: u8,
+        state_version: u64,
+    ) -> (Enc<Mxe, UserPosition>, u8, u64) {
+        let mut position = position_ctxt.to_arcis();
+        let book = book_ctxt.to_arcis();
+        let fills = fills_ctxt.to_arcis();
+
+        let mut is_buy = false;
+        let mut is_yes = false;
+        let mut shares = 0u64;
+        let mut usdc = 0u64;
+        for i in 0..MAX_LIMIT_ORDERS {
+            if (i as u8) == slot {
+                is_buy = book.is_buy[i];
+                is_yes = book.is_yes[i];
+                shares = fills.shares[i];
+                usdc = fills.usdc[i];
+            }
+        }
+
+        if is_buy {
+            if is_yes {
+                position.yes_tokens += shares;
+            } else {
+                position.no_tokens += shares;
+            }
+            position.net_usdc += usdc;
+        } else {
+            // Holdings may have shrunk since the order was placed
+            if is_yes {
+                let sold = if shares < position.yes_tokens { shares } else { position.yes_tokens };
+                position.yes_tokens -= sold;
+            } else {
+                let sold = if shares < position.no_tokens { shares } else { position.no_tokens };
+                position.no_tokens -= sold;
+            }
+            let returned = if usdc < position.net_usdc { usdc } else { position.net_usdc };
+            position.net_usdc -= returned;
+        }
+
+        // slot is echoed back so the callback can mark the order as applied
+        (position_ctxt.owner.from_arcis(position), slot, state_version)
     }
 }
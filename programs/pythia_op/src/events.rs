@@ -5,21 +5,75 @@ use crate::state::*;
 #[event]
 pub struct SponsorViewMarketEvent {
     pub market: Pubkey,
-    pub encrypted_state: [[u8; 32]; 4],
+    pub encrypted_state: [[u8; 32]; MARKET_STATE_ENCRYPTED_FIELDS],
+    pub nonce: u128,
+}
+
+#[event]
+pub struct SponsorMarketSummaryEvent {
+    pub market: Pubkey,
+    pub encrypted_summary: [[u8; 32]; MARKET_SUMMARY_ENCRYPTED_FIELDS],
     pub nonce: u128,
 }
 
 #[event]
 pub struct UserPositionViewEvent {
     pub user_position: Pubkey,
-    pub encrypted_state: [[u8; 32]; 4],
+    pub encrypted_state: [[u8; 32]; USER_POSITION_ENCRYPTED_FIELDS],
+    pub nonce: u128,
+}
+
+#[event]
+pub struct MyPositionViewEvent {
+    pub user_position: Pubkey,
+    pub user: Pubkey,
+    pub encrypted_state: [[u8; 32]; USER_POSITION_ENCRYPTED_FIELDS],
     pub nonce: u128,
 }
 
 #[event]
 pub struct MarketResolvedEvent {
     pub market: Pubkey,
-    pub outcome: bool,
+    pub outcome: u8,
+}
+
+#[event]
+pub struct MarketInvalidatedEvent {
+    pub market: Pubkey,
+}
+
+#[event]
+pub struct RefundEvent {
+    pub market: Pubkey,
+    pub user: Pubkey,
+}
+
+#[event]
+pub struct ResolutionProposedEvent {
+    pub market: Pubkey,
+    pub outcome: u8,
+    pub value: u64,
+    pub dispute_deadline: i64,
+}
+
+#[event]
+pub struct ResolutionDisputedEvent {
+    pub market: Pubkey,
+    pub disputer: Pubkey,
+}
+
+#[event]
+pub struct ScalarMarketResolvedEvent {
+    pub market: Pubkey,
+    pub value: u64,
+    pub long_payout: u64,   // In thousandths (0-1000)
+    pub short_payout: u64,  // In thousandths (0-1000)
+}
+
+#[event]
+pub struct MarketGroupCompletedEvent {
+    pub group: Pubkey,
+    pub resolved_yes: u8,
 }
 
 #[event]
@@ -36,4 +90,113 @@ pub struct WindowSwitchEvent {
     pub no_pool: u64,
     pub yes_price: u64,  // In thousandths (0-1000)
     pub no_price: u64,   // In thousandths (0-1000)
-}
\ No newline at end of file
+    pub sponsor_views: u32,  // Sponsor view requests on the market so far
+}
+
+#[event]
+pub struct SponsorActionApprovedEvent {
+    pub sponsor: Pubkey,
+    pub pending_action: Pubkey,
+    pub approver: Pubkey,
+    pub approvals: u8,
+}
+
+#[event]
+pub struct MarketAuthorityTransferredEvent {
+    pub market: Pubkey,
+    pub previous_authority: Pubkey,
+    pub new_authority: Pubkey,
+}
+
+#[event]
+pub struct SponsorStatsUpdated {
+    pub sponsor: Pubkey,
+    pub total_markets_created: u64,
+    pub markets_resolved_yes: u64,
+    pub markets_resolved_no: u64,
+    pub markets_resolved_invalid: u64,
+    pub resolutions_disputed: u64,
+    pub resolutions_overturned: u64,
+    pub average_resolution_secs: u64,
+    pub total_liquidity_provided: u64,
+}
+
+#[event]
+pub struct SponsorBondSlashedEvent {
+    pub sponsor: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+}
+
+#[event]
+pub struct ResearchViewEvent {
+    pub research_commitment: Pubkey,
+    pub encrypted_note: [[u8; 32]; RESEARCH_NOTE_ENCRYPTED_FIELDS],
+    pub nonce: u128,
+}
+
+#[event]
+pub struct ResearchRevealedEvent {
+    pub research_commitment: Pubkey,
+    pub market: Pubkey,
+    pub user: Pubkey,
+    pub trade_id: u64,
+    pub committed_ts: i64,
+    pub content_hash: [u8; 32],
+}
+
+#[event]
+pub struct TraderScoredEvent {
+    pub trader: Pubkey,
+    pub market: Pubkey,
+    pub staked_usdc: u64,
+    pub payout_usdc: u64,
+    pub entry_price: u64,  // In thousandths (0-1000)
+    pub brier: u64,        // In thousandths (0-1000), lower is better
+    pub average_brier: u64,
+    pub realized_pnl: i64,
+}
+
+#[event]
+pub struct AttentionHintEvent {
+    pub market: Pubkey,
+    pub level: AttentionLevel,
+}
+
+#[event]
+pub struct OrderBatchClearedEvent {
+    pub market: Pubkey,
+    pub epoch: u64,
+    pub order_count: u32,
+}
+
+#[event]
+pub struct LimitOrdersMatchedEvent {
+    pub market: Pubkey,
+    pub limit_order_book: Pubkey,
+    pub order_count: u8,
+}
+
+#[event]
+pub struct PositionHistogramViewEvent {
+    pub market: Pubkey,
+    pub position_aggregate: Pubkey,
+    pub positions_included: u32,
+    pub encrypted_histogram: [[u8; 32]; POSITION_HISTOGRAM_ENCRYPTED_FIELDS],
+    pub nonce: u128,
+}
+
+#[event]
+pub struct ComputationFailedEvent {
+    /// Market or user position the computation would have updated
+    pub account: Pubkey,
+    pub computation: ComputationKind,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ViewFeesWithdrawnEvent {
+    pub market: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+}
@@ -1,5 +1,7 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, TransferChecked};
 use arcium_anchor::prelude::*;
+use solana_sha256_hasher::hash;
 pub mod state;
 pub mod events;
 
@@ -10,13 +12,34 @@ pub use state::*;
 // Computation definition offsets for each encrypted instruction
 const COMP_DEF_OFFSET_INITIALIZE_MARKET: u32 = comp_def_offset("initialize_market");
 const COMP_DEF_OFFSET_INITIALIZE_USER_POSITION: u32 = comp_def_offset("initialize_user_position");
-const COMP_DEF_OFFSET_PROCESS_PRIVATE_TRADE: u32 = comp_def_offset("process_private_trade");
-const COMP_DEF_OFFSET_UPDATE_USER_POSITION: u32 = comp_def_offset("update_user_position");
 const COMP_DEF_OFFSET_CLOSE_POSITION: u32 = comp_def_offset("close_position");
 const COMP_DEF_OFFSET_REVEAL_MARKET_STATE: u32 = comp_def_offset("reveal_market_state");
 const COMP_DEF_OFFSET_HIDE_MARKET_STATE: u32 = comp_def_offset("hide_market_state");
 const COMP_DEF_OFFSET_VIEW_MARKET_STATE: u32 = comp_def_offset("view_market_state");
 const COMP_DEF_OFFSET_VIEW_USER_POSITION: u32 = comp_def_offset("view_user_position");
+const COMP_DEF_OFFSET_INITIALIZE_CATEGORICAL_MARKET: u32 = comp_def_offset("initialize_categorical_market");
+const COMP_DEF_OFFSET_INITIALIZE_CATEGORICAL_POSITION: u32 = comp_def_offset("initialize_categorical_position");
+const COMP_DEF_OFFSET_PROCESS_CATEGORICAL_TRADE: u32 = comp_def_offset("process_categorical_trade");
+const COMP_DEF_OFFSET_REVEAL_REFUND: u32 = comp_def_offset("reveal_refund");
+const COMP_DEF_OFFSET_REVEAL_CATEGORICAL_REFUND: u32 = comp_def_offset("reveal_categorical_refund");
+const COMP_DEF_OFFSET_COMMIT_RESEARCH: u32 = comp_def_offset("commit_research");
+const COMP_DEF_OFFSET_VIEW_RESEARCH: u32 = comp_def_offset("view_research");
+const COMP_DEF_OFFSET_REVEAL_RESEARCH: u32 = comp_def_offset("reveal_research");
+const COMP_DEF_OFFSET_SCORE_POSITION: u32 = comp_def_offset("score_position");
+const COMP_DEF_OFFSET_REVEAL_ATTENTION_HINT: u32 = comp_def_offset("reveal_attention_hint");
+const COMP_DEF_OFFSET_INITIALIZE_ORDER_BATCH: u32 = comp_def_offset("initialize_order_batch");
+const COMP_DEF_OFFSET_SUBMIT_BATCH_ORDER: u32 = comp_def_offset("submit_batch_order");
+const COMP_DEF_OFFSET_CLEAR_ORDER_BATCH: u32 = comp_def_offset("clear_order_batch");
+const COMP_DEF_OFFSET_FILL_BATCH_ORDER: u32 = comp_def_offset("fill_batch_order");
+const COMP_DEF_OFFSET_INITIALIZE_LIMIT_ORDER_BOOK: u32 = comp_def_offset("initialize_limit_order_book");
+const COMP_DEF_OFFSET_PLACE_LIMIT_ORDER: u32 = comp_def_offset("place_limit_order");
+const COMP_DEF_OFFSET_MATCH_LIMIT_ORDERS: u32 = comp_def_offset("match_limit_orders");
+const COMP_DEF_OFFSET_APPLY_LIMIT_FILL: u32 = comp_def_offset("apply_limit_fill");
+const COMP_DEF_OFFSET_VIEW_MY_POSITION: u32 = comp_def_offset("view_my_position");
+const COMP_DEF_OFFSET_VIEW_MARKET_SUMMARY: u32 = comp_def_offset("view_market_summary");
+const COMP_DEF_OFFSET_INITIALIZE_POSITION_HISTOGRAM: u32 = comp_def_offset("initialize_position_histogram");
+const COMP_DEF_OFFSET_ADD_POSITION_TO_HISTOGRAM: u32 = comp_def_offset("add_position_to_histogram");
+const COMP_DEF_OFFSET_VIEW_POSITION_HISTOGRAM: u32 = comp_def_offset("view_position_histogram");
 
 declare_id!("DNBCB9WzLruKzvdFYpoEXNr9bVTTJ8eCeNMzKHhZRP1m");
 
@@ -37,6 +60,16 @@ pub mod pythia_op {
         sponsor.is_whitelisted = false; 
         sponsor.creation_date = clock.unix_timestamp;
         sponsor.total_markets_created = 0;
+        sponsor.signers = Vec::new();
+        sponsor.threshold = 0;
+        sponsor.markets_resolved = 0;
+        sponsor.markets_resolved_yes = 0;
+        sponsor.markets_resolved_no = 0;
+        sponsor.markets_resolved_invalid = 0;
+        sponsor.resolutions_disputed = 0;
+        sponsor.resolutions_overturned = 0;
+        sponsor.total_resolution_secs = 0;
+        sponsor.total_liquidity_provided = 0;
 
         Ok(())
     }
@@ -44,27 +77,268 @@ pub mod pythia_op {
     pub fn whitelist_sponsor(
         ctx: Context<WhitelistSponsor>,
     ) -> Result<()> {
-        // TODO: Add proper admin authority check
+        // Admin authority is enforced by has_one on the program config
         ctx.accounts.sponsor.is_whitelisted = true;
         Ok(())
     }
 
-    pub fn init_initialize_market_comp_def(ctx: Context<InitInitializeMarketCompDef>) -> Result<()> {
-        init_comp_def(ctx.accounts, 0, None, None)?;
+    pub fn set_sponsor_signers(
+        ctx: Context<SetSponsorSigners>,
+        signers: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        require!(
+            signers.len() <= MAX_SPONSOR_SIGNERS && threshold as usize <= signers.len(),
+            ErrorCode::InvalidSignerConfig
+        );
+
+        // Changing an existing signer set needs the current signers' approval
+        let approved = consume_sponsor_approval(
+            &ctx.accounts.sponsor_account,
+            ctx.accounts.authority.key(),
+            ctx.accounts.pending_action.as_mut(),
+            SponsorAction::SetSigners {
+                signers_hash: hash_signers(&signers),
+                threshold,
+            },
+        )?;
+        if !approved {
+            require_keys_eq!(
+                ctx.accounts.authority.key(),
+                ctx.accounts.sponsor_account.authority,
+                ErrorCode::Unauthorized
+            );
+        }
+
+        let sponsor = &mut ctx.accounts.sponsor_account;
+        sponsor.signers = signers;
+        sponsor.threshold = threshold;
         Ok(())
     }
 
-    pub fn init_initialize_user_position_comp_def(ctx: Context<InitInitializeUserPositionCompDef>) -> Result<()> {
-        init_comp_def(ctx.accounts, 0, None, None)?;
+    pub fn create_pending_action(
+        ctx: Context<CreatePendingAction>,
+        action_id: u64,
+        action: SponsorAction,
+    ) -> Result<()> {
+        let sponsor = &ctx.accounts.sponsor_account;
+        let signer = ctx.accounts.signer.key();
+        let clock = Clock::get()?;
+
+        require!(sponsor.is_multisig(), ErrorCode::NotMultisig);
+        require!(sponsor.is_signer(&signer), ErrorCode::Unauthorized);
+
+        let pending_action = &mut ctx.accounts.pending_action;
+        pending_action.bump = ctx.bumps.pending_action;
+        pending_action.sponsor = sponsor.key();
+        pending_action.action_id = action_id;
+        pending_action.action = action;
+        // Creating an action counts as the creator's approval
+        pending_action.approvals = vec![signer];
+        pending_action.created_ts = clock.unix_timestamp;
+        pending_action.executed = false;
+
+        emit!(SponsorActionApprovedEvent {
+            sponsor: sponsor.key(),
+            pending_action: pending_action.key(),
+            approver: signer,
+            approvals: 1,
+        });
+
+        Ok(())
+    }
+
+    pub fn approve_pending_action(
+        ctx: Context<ApprovePendingAction>,
+    ) -> Result<()> {
+        let sponsor = &ctx.accounts.sponsor_account;
+        let signer = ctx.accounts.signer.key();
+        let pending_action = &mut ctx.accounts.pending_action;
+
+        require!(sponsor.is_signer(&signer), ErrorCode::Unauthorized);
+        require!(!pending_action.executed, ErrorCode::ActionAlreadyExecuted);
+        require!(
+            !pending_action.approvals.contains(&signer),
+            ErrorCode::AlreadyApproved
+        );
+
+        pending_action.approvals.push(signer);
+
+        emit!(SponsorActionApprovedEvent {
+            sponsor: sponsor.key(),
+            pending_action: pending_action.key(),
+            approver: signer,
+            approvals: pending_action.approvals.len() as u8,
+        });
+
+        Ok(())
+    }
+
+    pub fn init_config(
+        ctx: Context<InitConfig>,
+        dispute_period: i64,
+        resolution_bond: u64,
+        min_sponsor_bond: u64,
+        unstake_cooldown: i64,
+    ) -> Result<()> {
+        require!(dispute_period > 0, ErrorCode::InvalidConfig);
+        require!(unstake_cooldown >= 0, ErrorCode::InvalidConfig);
+
+        let config = &mut ctx.accounts.config;
+        config.bump = ctx.bumps.config;
+        config.admin = ctx.accounts.admin.key();
+        config.dispute_period = dispute_period;
+        config.resolution_bond = resolution_bond;
+        config.min_sponsor_bond = min_sponsor_bond;
+        config.unstake_cooldown = unstake_cooldown;
+        config.usdc_mint = ctx.accounts.usdc_mint.key();
+        Ok(())
+    }
+
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        dispute_period: i64,
+        resolution_bond: u64,
+        min_sponsor_bond: u64,
+        unstake_cooldown: i64,
+    ) -> Result<()> {
+        require!(dispute_period > 0, ErrorCode::InvalidConfig);
+        require!(unstake_cooldown >= 0, ErrorCode::InvalidConfig);
+
+        let config = &mut ctx.accounts.config;
+        config.dispute_period = dispute_period;
+        config.resolution_bond = resolution_bond;
+        config.min_sponsor_bond = min_sponsor_bond;
+        config.unstake_cooldown = unstake_cooldown;
+        Ok(())
+    }
+
+    pub fn stake_sponsor_bond(
+        ctx: Context<StakeSponsorBond>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.sponsor_bond.unstake_requested_ts.is_none(),
+            ErrorCode::UnstakePending
+        );
+
+        transfer_usdc(
+            &ctx.accounts.authority_token_account,
+            &ctx.accounts.bond_vault,
+            ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.usdc_mint,
+            &ctx.accounts.token_program,
+            amount,
+            &[],
+        )?;
+
+        let sponsor_bond = &mut ctx.accounts.sponsor_bond;
+        sponsor_bond.bump = ctx.bumps.sponsor_bond;
+        sponsor_bond.sponsor = ctx.accounts.sponsor_account.key();
+        sponsor_bond.amount = sponsor_bond.amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    /// Starts the unstake cooldown. The sponsor cannot create markets until the bond is withdrawn.
+    pub fn request_sponsor_unstake(
+        ctx: Context<UpdateSponsorBond>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let sponsor_bond = &mut ctx.accounts.sponsor_bond;
+
+        require!(
+            sponsor_bond.unstake_requested_ts.is_none(),
+            ErrorCode::UnstakePending
+        );
+        require!(
+            ctx.accounts.sponsor_account.open_markets() == 0,
+            ErrorCode::SponsorHasOpenMarkets
+        );
+
+        sponsor_bond.unstake_requested_ts = Some(clock.unix_timestamp);
+        Ok(())
+    }
+
+    pub fn withdraw_sponsor_bond(
+        ctx: Context<WithdrawSponsorBond>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let sponsor_bond = &ctx.accounts.sponsor_bond;
+
+        require!(
+            sponsor_bond.unstake_requested_ts.is_some(),
+            ErrorCode::UnstakeNotRequested
+        );
+        require!(
+            sponsor_bond.cooldown_elapsed(clock.unix_timestamp, ctx.accounts.config.unstake_cooldown),
+            ErrorCode::UnstakeCooldown
+        );
+        require!(
+            ctx.accounts.sponsor_account.open_markets() == 0,
+            ErrorCode::SponsorHasOpenMarkets
+        );
+
+        let amount = sponsor_bond.amount;
+        let sponsor_key = ctx.accounts.sponsor_account.key();
+        let bump = [sponsor_bond.bump];
+        let seeds: &[&[u8]] = &[b"sponsor_bond", sponsor_key.as_ref(), &bump];
+        transfer_usdc(
+            &ctx.accounts.bond_vault,
+            &ctx.accounts.authority_token_account,
+            ctx.accounts.sponsor_bond.to_account_info(),
+            &ctx.accounts.usdc_mint,
+            &ctx.accounts.token_program,
+            amount,
+            &[seeds],
+        )?;
+
+        let sponsor_bond = &mut ctx.accounts.sponsor_bond;
+        sponsor_bond.amount = 0;
+        sponsor_bond.unstake_requested_ts = None;
+        Ok(())
+    }
+
+    /// Admin slash after a dispute overturned one of the sponsor's resolutions.
+    /// Each overturned resolution can be slashed once.
+    pub fn slash_sponsor_bond(
+        ctx: Context<SlashSponsorBond>,
+        amount: u64,
+    ) -> Result<()> {
+        let resolutions_overturned = ctx.accounts.sponsor_account.resolutions_overturned;
+        let slashed = ctx.accounts.sponsor_bond
+            .apply_slash(amount, resolutions_overturned)
+            .ok_or(ErrorCode::NothingToSlash)?;
+
+        let sponsor_key = ctx.accounts.sponsor_account.key();
+        let bump = [ctx.accounts.sponsor_bond.bump];
+        let seeds: &[&[u8]] = &[b"sponsor_bond", sponsor_key.as_ref(), &bump];
+        transfer_usdc(
+            &ctx.accounts.bond_vault,
+            &ctx.accounts.recipient_token_account,
+            ctx.accounts.sponsor_bond.to_account_info(),
+            &ctx.accounts.usdc_mint,
+            &ctx.accounts.token_program,
+            slashed,
+            &[seeds],
+        )?;
+
+        emit!(SponsorBondSlashedEvent {
+            sponsor: ctx.accounts.sponsor_bond.sponsor,
+            amount: slashed,
+            remaining: ctx.accounts.sponsor_bond.amount,
+        });
+
         Ok(())
     }
 
-    pub fn init_process_private_trade_comp_def(ctx: Context<InitProcessPrivateTradeCompDef>) -> Result<()> {
+    pub fn init_initialize_market_comp_def(ctx: Context<InitInitializeMarketCompDef>) -> Result<()> {
         init_comp_def(ctx.accounts, 0, None, None)?;
         Ok(())
     }
 
-    pub fn init_update_user_position_comp_def(ctx: Context<InitUpdateUserPositionCompDef>) -> Result<()> {
+    pub fn init_initialize_user_position_comp_def(ctx: Context<InitInitializeUserPositionCompDef>) -> Result<()> {
         init_comp_def(ctx.accounts, 0, None, None)?;
         Ok(())
     }
@@ -99,46 +373,164 @@ pub mod pythia_op {
         Ok(())
     }
 
+    pub fn init_initialize_categorical_market_comp_def(ctx: Context<InitInitializeCategoricalMarketCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_initialize_categorical_position_comp_def(ctx: Context<InitInitializeCategoricalPositionCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_process_categorical_trade_comp_def(ctx: Context<InitProcessCategoricalTradeCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_reveal_refund_comp_def(ctx: Context<InitRevealRefundCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_reveal_categorical_refund_comp_def(ctx: Context<InitRevealCategoricalRefundCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_commit_research_comp_def(ctx: Context<InitCommitResearchCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_view_research_comp_def(ctx: Context<InitViewResearchCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_reveal_research_comp_def(ctx: Context<InitRevealResearchCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_score_position_comp_def(ctx: Context<InitScorePositionCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_reveal_attention_hint_comp_def(ctx: Context<InitRevealAttentionHintCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_initialize_order_batch_comp_def(ctx: Context<InitInitializeOrderBatchCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_submit_batch_order_comp_def(ctx: Context<InitSubmitBatchOrderCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_clear_order_batch_comp_def(ctx: Context<InitClearOrderBatchCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_fill_batch_order_comp_def(ctx: Context<InitFillBatchOrderCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_initialize_limit_order_book_comp_def(ctx: Context<InitInitializeLimitOrderBookCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_place_limit_order_comp_def(ctx: Context<InitPlaceLimitOrderCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_match_limit_orders_comp_def(ctx: Context<InitMatchLimitOrdersCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_apply_limit_fill_comp_def(ctx: Context<InitApplyLimitFillCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_view_my_position_comp_def(ctx: Context<InitViewMyPositionCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_view_market_summary_comp_def(ctx: Context<InitViewMarketSummaryCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_initialize_position_histogram_comp_def(ctx: Context<InitInitializePositionHistogramCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_add_position_to_histogram_comp_def(ctx: Context<InitAddPositionToHistogramCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, 0, None, None)?;
+        Ok(())
+    }
+
+    pub fn init_view_position_histogram_comp_def(ctx: Context<InitViewPositionHistogramCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, 0, None, None)?;
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn init_market(
-        ctx: Context<InitMarket>,
+        mut ctx: Context<InitMarket>,
         question: String,
         resolution_date: i64,
         liquidity_cap: u64,
         initial_liquidity_usdc: u64,
         opp_window_duration: u64,
         pub_window_duration: u64,
+        max_views_per_window: u32,
+        view_fee_usdc: u64,
+        min_view_traders: u32,
+        window_schedule: Vec<WindowScheduleEntry>,
     ) -> Result<()> {
-        let market = &mut ctx.accounts.market;
-        let clock = Clock::get()?;
-
-        require!(
-            ctx.accounts.sponsor_account.is_whitelisted,
-            ErrorCode::SponsorNotWhitelisted
-        );
-
-        let sponsor_key = ctx.accounts.sponsor_account.key();
-        let sponsor_account = &mut ctx.accounts.sponsor_account;
-
-        market.bump = ctx.bumps.market;
-        market.sponsor = sponsor_key;
-        market.authority = ctx.accounts.sponsor.key(); // Sponsor authority is initial authority
-        market.question = question;
-        market.resolution_date = resolution_date;
-        market.window_state = MarketWindow::Private;
-        market.liquidity_cap = liquidity_cap;
-        market.initial_liquidity_usdc = initial_liquidity_usdc;
-        market.nonce = 0;
-        market.opp_window_duration = opp_window_duration;
-        market.pub_window_duration = pub_window_duration;
-        market.last_switch_ts = clock.unix_timestamp;
-        market.resolved = false;
-        market.outcome = None;
-        // Encrypted market state: [yes_pool, no_pool, last_price, total_trades] as 32-byte ciphertexts
-        market.market_state = [[0; 32]; 4];
-
-        sponsor_account.total_markets_created += 1;
+        let accounts = &mut ctx.accounts;
+        authorize_market_creation(
+            &accounts.sponsor,
+            &accounts.sponsor_account,
+            &accounts.sponsor_bond,
+            &accounts.config,
+            accounts.pending_action.as_mut(),
+            &question,
+        )?;
 
-        Ok(())
+        init_market_common(
+            &mut accounts.market,
+            ctx.bumps.market,
+            &mut accounts.sponsor_account,
+            accounts.sponsor.key(), // Sponsor authority is initial authority
+            MarketType::Binary,
+            MarketParams {
+                question,
+                resolution_date,
+                liquidity_cap,
+                initial_liquidity_usdc,
+                opp_window_duration,
+                pub_window_duration,
+                max_views_per_window,
+                view_fee_usdc,
+                min_view_traders,
+                window_schedule,
+            },
+        )
     }
 
     pub fn init_market_encrypted(
@@ -149,6 +541,11 @@ pub mod pythia_op {
     ) -> Result<()> {
         use arcium_client::idl::arcium::types::CallbackAccount;
         
+        require!(
+            ctx.accounts.market.has_binary_pools(),
+            ErrorCode::WrongMarketType
+        );
+        
         ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
         
         let args = vec![
@@ -182,6 +579,8 @@ pub mod pythia_op {
         };
         ctx.accounts.market.market_state = o.ciphertexts;
         ctx.accounts.market.nonce = o.nonce;
+        // Markets start in the private window, which opens once its encrypted state exists
+        ctx.accounts.market.private_window_opened = true;
         Ok(())
     }
 
@@ -192,11 +591,27 @@ pub mod pythia_op {
     ) -> Result<()> {
         use arcium_client::idl::arcium::types::CallbackAccount;
         
+        require!(
+            ctx.accounts.market.has_binary_pools(),
+            ErrorCode::WrongMarketType
+        );
+        
         ctx.accounts.user_position.bump = ctx.bumps.user_position;
         ctx.accounts.user_position.user = ctx.accounts.user.key();
         ctx.accounts.user_position.market = ctx.accounts.market.key();
         ctx.accounts.user_position.nonce = 0;
-        ctx.accounts.user_position.position_state = [[0; 32]; 4];
+        ctx.accounts.user_position.position_state = [[0; 32]; USER_POSITION_ENCRYPTED_FIELDS];
+        ctx.accounts.user_position.refund_usdc = None;
+        ctx.accounts.user_position.refund_pending = false;
+        ctx.accounts.user_position.scored = false;
+        ctx.accounts.user_position.last_window_ts = 0;
+        ctx.accounts.user_position.aggregate_round = 0;
+        ctx.accounts.user_position.failed_updates = 0;
+        ctx.accounts.user_position.last_failure_ts = 0;
+        ctx.accounts.user_position.trade_count = 0;
+        ctx.accounts.user_position.last_trade_ts = 0;
+        ctx.accounts.user_position.state_version = 0;
+        ctx.accounts.market.position_count += 1;
         
         ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
         
@@ -230,41 +645,46 @@ pub mod pythia_op {
         };
         ctx.accounts.user_position.position_state = o.ciphertexts;
         ctx.accounts.user_position.nonce = o.nonce;
+        // Updates queued before the position existed computed from an empty state
+        ctx.accounts.user_position.state_version += 1;
         Ok(())
     }
 
-    pub fn trade_private(
-        ctx: Context<TradePrivate>,
+    pub fn close_position_private(
+        ctx: Context<ClosePositionPrivate>,
         computation_offset: u64,
-        trade_ciphertext: [u8; 32],
-        trade_pub_key: [u8; 32],
-        trade_nonce: u128,
+        close_ciphertext: [u8; 32],
+        close_pub_key: [u8; 32],
+        close_nonce: u128,
     ) -> Result<()> {
         use arcium_client::idl::arcium::types::CallbackAccount;
         
         let market = &ctx.accounts.market;
         
-        // Assert we're in private window
+        require!(
+            market.has_binary_pools(),
+            ErrorCode::WrongMarketType
+        );
+        require!(!market.trading_frozen, ErrorCode::TradingFrozen);
+        require!(!market.pending_transition, ErrorCode::TransitionPending);
         require!(
             market.window_state == MarketWindow::Private,
             ErrorCode::WrongWindowState
         );
-
+        
         ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
         
-        // Queue the private trade computation
         let args = vec![
-            Argument::PlaintextU128(ctx.accounts.market.nonce),
+            Argument::PlaintextU128(ctx.accounts.user_position.nonce),
             Argument::Account(
-                ctx.accounts.market.key(),
-                // Offset: 8 (discriminator) + 1 (bump) + 32 (sponsor) + 32 (authority) + (4 + 200) (question)
-                // + 8 (resolution_date) + 1 (window_state) + 8 (liquidity_cap)
-                8 + 1 + 32 + 32 + (4 + 200) + 8 + 1 + 8,
-                32 * 4, // 4 encrypted fields (yes_pool, no_pool, last_price, total_trades)
+                ctx.accounts.user_position.key(),
+                USER_POSITION_STATE_OFFSET,
+                USER_POSITION_STATE_LEN, // yes_tokens, no_tokens, yes_tokens_closed, no_tokens_closed
             ),
-            Argument::ArcisPubkey(trade_pub_key),
-            Argument::PlaintextU128(trade_nonce),
-            Argument::EncryptedU8(trade_ciphertext),
+            Argument::ArcisPubkey(close_pub_key),
+            Argument::PlaintextU128(close_nonce),
+            Argument::EncryptedU8(close_ciphertext),
+            Argument::PlaintextU64(ctx.accounts.user_position.state_version),
         ];
 
         queue_computation(
@@ -272,8 +692,8 @@ pub mod pythia_op {
             computation_offset,
             args,
             None,
-            vec![ProcessPrivateTradeCallback::callback_ix(&[CallbackAccount {
-                pubkey: ctx.accounts.market.key(),
+            vec![ClosePositionCallback::callback_ix(&[CallbackAccount {
+                pubkey: ctx.accounts.user_position.key(),
                 is_writable: true,
             }])],
             1,
@@ -282,154 +702,60 @@ pub mod pythia_op {
         Ok(())
     }
 
-    #[arcium_callback(encrypted_ix = "process_private_trade")]
-    pub fn process_private_trade_callback(
-        ctx: Context<ProcessPrivateTradeCallback>,
-        output: ComputationOutputs<ProcessPrivateTradeOutput>,
+    #[arcium_callback(encrypted_ix = "close_position")]
+    pub fn close_position_callback(
+        ctx: Context<ClosePositionCallback>,
+        output: ComputationOutputs<ClosePositionOutput>,
     ) -> Result<()> {
         let o = match output {
-            ComputationOutputs::Success(ProcessPrivateTradeOutput { field_0 }) => field_0,
-            _ => return Err(ErrorCode::AbortedComputation.into()),
+            ComputationOutputs::Success(ClosePositionOutput { field_0 }) => field_0,
+            _ => {
+                return record_position_failure(
+                    &mut ctx.accounts.user_position,
+                    ComputationKind::ClosePosition,
+                );
+            }
         };
-        ctx.accounts.market.market_state = o.ciphertexts;
-        ctx.accounts.market.nonce = o.nonce;
+        if !advance_position_version(&mut ctx.accounts.user_position, o.field_1) {
+            return record_position_failure(
+                &mut ctx.accounts.user_position,
+                ComputationKind::ClosePosition,
+            );
+        }
         
-        emit!(TradeEvent {
-            market: ctx.accounts.market.key(),
-            window: MarketWindow::Private,
+        let clock = Clock::get()?;
+        let user_position = &mut ctx.accounts.user_position;
+        
+        user_position.position_state = o.field_0.ciphertexts;
+        user_position.nonce = o.field_0.nonce;
+        
+        // Add close record (price will be updated during window switch)
+        user_position.close_records.push(CloseRecord {
+            timestamp: clock.unix_timestamp,
+            yes_shares_closed: 0,  // Will be revealed in public window
+            no_shares_closed: 0,   // Will be revealed in public window
+            market_price_at_close: 0,  // Will be set during window switch
         });
         
         Ok(())
     }
 
-    pub fn update_user_position_private(
-        ctx: Context<UpdateUserPositionPrivate>,
+    pub fn switch_to_public(
+        ctx: Context<SwitchToPublic>,
         computation_offset: u64,
-        trade_ciphertext: [u8; 32],
-        trade_pub_key: [u8; 32],
-        trade_nonce: u128,
     ) -> Result<()> {
         use arcium_client::idl::arcium::types::CallbackAccount;
         
-        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        let market = &ctx.accounts.market;
+        let clock = Clock::get()?;
         
-        // Queue the user position update computation
-        let args = vec![
-            Argument::PlaintextU128(ctx.accounts.user_position.nonce),
-            Argument::Account(
-                ctx.accounts.user_position.key(),
-                // Offset: 8 (discriminator) + 1 (bump) + 32 (user) + 32 (market)
-                8 + 1 + 32 + 32,
-                32 * 2, // 2 encrypted fields (yes_tokens, no_tokens)
-            ),
-            Argument::ArcisPubkey(trade_pub_key),
-            Argument::PlaintextU128(trade_nonce),
-            Argument::EncryptedU8(trade_ciphertext),
-        ];
-
-        queue_computation(
-            ctx.accounts,
-            computation_offset,
-            args,
-            None,
-            vec![UpdateUserPositionCallback::callback_ix(&[CallbackAccount {
-                pubkey: ctx.accounts.user_position.key(),
-                is_writable: true,
-            }])],
-            1,
-        )?;
-
-        Ok(())
-    }
-
-    #[arcium_callback(encrypted_ix = "update_user_position")]
-    pub fn update_user_position_callback(
-        ctx: Context<UpdateUserPositionCallback>,
-        output: ComputationOutputs<UpdateUserPositionOutput>,
-    ) -> Result<()> {
-        let o = match output {
-            ComputationOutputs::Success(UpdateUserPositionOutput { field_0 }) => field_0,
-            _ => return Err(ErrorCode::AbortedComputation.into()),
-        };
-        ctx.accounts.user_position.position_state = o.ciphertexts;
-        ctx.accounts.user_position.nonce = o.nonce;
-        Ok(())
-    }
-
-    pub fn close_position_private(
-        ctx: Context<ClosePositionPrivate>,
-        computation_offset: u64,
-        close_ciphertext: [u8; 32],
-        close_pub_key: [u8; 32],
-        close_nonce: u128,
-    ) -> Result<()> {
-        use arcium_client::idl::arcium::types::CallbackAccount;
-        
-        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
-        
-        let args = vec![
-            Argument::PlaintextU128(ctx.accounts.user_position.nonce),
-            Argument::Account(
-                ctx.accounts.user_position.key(),
-                // Offset: 8 (discriminator) + 1 (bump) + 32 (user) + 32 (market)
-                8 + 1 + 32 + 32,
-                32 * 4, // 4 encrypted fields (yes_tokens, no_tokens, yes_tokens_closed, no_tokens_closed)
-            ),
-            Argument::ArcisPubkey(close_pub_key),
-            Argument::PlaintextU128(close_nonce),
-            Argument::EncryptedU8(close_ciphertext),
-        ];
-
-        queue_computation(
-            ctx.accounts,
-            computation_offset,
-            args,
-            None,
-            vec![ClosePositionCallback::callback_ix(&[CallbackAccount {
-                pubkey: ctx.accounts.user_position.key(),
-                is_writable: true,
-            }])],
-            1,
-        )?;
-
-        Ok(())
-    }
-
-    #[arcium_callback(encrypted_ix = "close_position")]
-    pub fn close_position_callback(
-        ctx: Context<ClosePositionCallback>,
-        output: ComputationOutputs<ClosePositionOutput>,
-    ) -> Result<()> {
-        let o = match output {
-            ComputationOutputs::Success(ClosePositionOutput { field_0 }) => field_0,
-            _ => return Err(ErrorCode::AbortedComputation.into()),
-        };
-        
-        let clock = Clock::get()?;
-        let user_position = &mut ctx.accounts.user_position;
-        
-        user_position.position_state = o.ciphertexts;
-        user_position.nonce = o.nonce;
-        
-        // Add close record (price will be updated during window switch)
-        user_position.close_records.push(CloseRecord {
-            timestamp: clock.unix_timestamp,
-            yes_shares_closed: 0,  // Will be revealed in public window
-            no_shares_closed: 0,   // Will be revealed in public window
-            market_price_at_close: 0,  // Will be set during window switch
-        });
-        
-        Ok(())
-    }
-
-    pub fn switch_to_public(
-        ctx: Context<SwitchToPublic>,
-        computation_offset: u64,
-    ) -> Result<()> {
-        use arcium_client::idl::arcium::types::CallbackAccount;
-        
-        let market = &ctx.accounts.market;
-        let clock = Clock::get()?;
+        // Categorical markets stay in the private window until resolution
+        require!(
+            market.has_binary_pools(),
+            ErrorCode::WrongMarketType
+        );
+        require!(!market.trading_frozen, ErrorCode::TradingFrozen);
+        require!(!market.pending_transition, ErrorCode::TransitionPending);
         
         // Assert we're in private window
         require!(
@@ -439,9 +765,21 @@ pub mod pythia_op {
         
         // Check if opportunity window has expired
         require!(
-            clock.unix_timestamp >= market.last_switch_ts + market.opp_window_duration as i64,
+            market.window_expired(clock.unix_timestamp),
             ErrorCode::WindowNotExpired
         );
+        // Limit orders must execute before prices are revealed
+        require!(
+            market.open_limit_book.is_none(),
+            ErrorCode::LimitOrdersNotMatched
+        );
+        // So must batched orders
+        require_batch_cleared(&ctx.accounts.order_batch)?;
+        
+        // Published with the reveal so traders see how often the sponsor looked
+        ctx.accounts.market.public_sponsor_views =
+            sponsor_view_count(&ctx.accounts.sponsor_access_log)?;
+        ctx.accounts.market.pending_transition = true;
 
         ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
         
@@ -450,10 +788,10 @@ pub mod pythia_op {
             Argument::PlaintextU128(ctx.accounts.market.nonce),
             Argument::Account(
                 ctx.accounts.market.key(),
-                // Offset to market_state
-                8 + 1 + 32 + 32 + (4 + 200) + 8 + 1 + 8,
-                32 * 4, // 4 encrypted fields
+                MARKET_STATE_OFFSET,
+                MARKET_STATE_LEN,
             ),
+            Argument::PlaintextU64(ctx.accounts.market.state_version),
         ];
 
         queue_computation(
@@ -480,11 +818,23 @@ pub mod pythia_op {
     ) -> Result<()> {
         let o = match output {
             ComputationOutputs::Success(RevealMarketStateOutput { field_0 }) => field_0,
-            _ => return Err(ErrorCode::AbortedComputation.into()),
+            _ => {
+                // Window is left unchanged so the switch can be queued again
+                ctx.accounts.market.pending_transition = false;
+                return record_market_failure(
+                    &mut ctx.accounts.market,
+                    ComputationKind::RevealMarketState,
+                );
+            }
         };
         
         let clock = Clock::get()?;
         let market = &mut ctx.accounts.market;
+        market.pending_transition = false;
+        if !advance_state_version(market, o.field_5) {
+            // A computation queued before the switch landed first; retry on the new state
+            return record_market_failure(market, ComputationKind::RevealMarketState);
+        }
         
         // Store revealed state: yes_pool, no_pool, yes_price, no_price
         // Note: total_trades is missing from current circuit output - circuits need rebuilding
@@ -495,7 +845,9 @@ pub mod pythia_op {
         market.public_total_trades = 0; // TODO: Rebuild circuits to include this field
         market.window_state = MarketWindow::Public;
         market.last_switch_ts = clock.unix_timestamp;
-        market.market_state = [[0; 32]; 4]; // Clear encrypted state
+        market.advance_window_schedule();
+        market.market_state = [[0; 32]; MARKET_STATE_ENCRYPTED_FIELDS]; // Clear encrypted state
+        market.window_sponsor_views = 0;
         
         emit!(WindowSwitchEvent {
             market: market.key(),
@@ -504,6 +856,7 @@ pub mod pythia_op {
             no_pool: o.field_1,
             yes_price: o.field_2,
             no_price: o.field_3,
+            sponsor_views: market.public_sponsor_views,
         });
         
         Ok(())
@@ -516,6 +869,13 @@ pub mod pythia_op {
     ) -> Result<()> {
         let market = &mut ctx.accounts.market;
         
+        require!(
+            market.has_binary_pools(),
+            ErrorCode::WrongMarketType
+        );
+        require!(!market.trading_frozen, ErrorCode::TradingFrozen);
+        require!(!market.pending_transition, ErrorCode::TransitionPending);
+        
         // Assert we're in public window
         require!(
             market.window_state == MarketWindow::Public,
@@ -571,6 +931,9 @@ pub mod pythia_op {
         let market = &ctx.accounts.market;
         let clock = Clock::get()?;
         
+        require!(!market.trading_frozen, ErrorCode::TradingFrozen);
+        require!(!market.pending_transition, ErrorCode::TransitionPending);
+        
         // Assert we're in public window
         require!(
             market.window_state == MarketWindow::Public,
@@ -579,7 +942,7 @@ pub mod pythia_op {
         
         // Check if public window has expired
         require!(
-            clock.unix_timestamp >= market.last_switch_ts + market.pub_window_duration as i64,
+            market.window_expired(clock.unix_timestamp),
             ErrorCode::WindowNotExpired
         );
 
@@ -591,7 +954,9 @@ pub mod pythia_op {
             Argument::PlaintextU64(market.public_yes_pool),
             Argument::PlaintextU64(market.public_no_pool),
             Argument::PlaintextU64(market.public_total_trades),
+            Argument::PlaintextU64(market.state_version),
         ];
+        ctx.accounts.market.pending_transition = true;
 
         queue_computation(
             ctx.accounts,
@@ -615,21 +980,38 @@ pub mod pythia_op {
     ) -> Result<()> {
         let o = match output {
             ComputationOutputs::Success(HideMarketStateOutput { field_0 }) => field_0,
-            _ => return Err(ErrorCode::AbortedComputation.into()),
+            _ => {
+                // Window is left unchanged so the switch can be queued again
+                ctx.accounts.market.pending_transition = false;
+                return record_market_failure(
+                    &mut ctx.accounts.market,
+                    ComputationKind::HideMarketState,
+                );
+            }
         };
         
         let clock = Clock::get()?;
         let market = &mut ctx.accounts.market;
+        market.pending_transition = false;
+        if !advance_state_version(market, o.field_1) {
+            // A computation queued before the switch landed first; retry on the new state
+            return record_market_failure(market, ComputationKind::HideMarketState);
+        }
         
-        market.market_state = o.ciphertexts;
-        market.nonce = o.nonce;
+        market.market_state = o.field_0.ciphertexts;
+        market.nonce = o.field_0.nonce;
         market.window_state = MarketWindow::Private;
+        market.private_window_opened = true;
         market.last_switch_ts = clock.unix_timestamp;
+        market.advance_window_schedule();
         market.public_yes_pool = 0;
         market.public_no_pool = 0;
         market.public_yes_price = 0;
         market.public_no_price = 0;
         market.public_total_trades = 0;
+        market.public_sponsor_views = 0;
+        market.window_traders = 0;
+        market.window_sponsor_views = 0;
         
         emit!(WindowSwitchEvent {
             market: market.key(),
@@ -638,6 +1020,7 @@ pub mod pythia_op {
             no_pool: 0,
             yes_price: 0,
             no_price: 0,
+            sponsor_views: 0,
         });
         
         Ok(())
@@ -648,12 +1031,12 @@ pub mod pythia_op {
         computation_offset: u64,
         sponsor_pub_key: [u8; 32],
     ) -> Result<()> {
+        // Sponsor authorization is enforced by the GetSponsorView account constraints
         let market = &ctx.accounts.market;
         
-        // Only sponsor can view
         require!(
-            ctx.accounts.sponsor.key() == market.sponsor,
-            ErrorCode::Unauthorized
+            market.has_binary_pools(),
+            ErrorCode::WrongMarketType
         );
         
         // Only works in private window
@@ -661,15 +1044,32 @@ pub mod pythia_op {
             market.window_state == MarketWindow::Private,
             ErrorCode::WrongWindowState
         );
+        charge_sponsor_view(
+            &mut ctx.accounts.market,
+            &ctx.accounts.sponsor,
+            ctx.accounts.sponsor_token_account.as_deref(),
+            ctx.accounts.market_vault.as_deref(),
+            ctx.accounts.usdc_mint.as_deref(),
+            ctx.accounts.token_program.as_ref(),
+        )?;
+        log_sponsor_view(
+            &mut ctx.accounts.sponsor_access_log,
+            ctx.bumps.sponsor_access_log,
+            ctx.accounts.market.key(),
+            ctx.accounts.sponsor.key(),
+            SponsorViewKind::MarketState,
+            ctx.accounts.market.key(),
+        )?;
 
         ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
         
         // Queue the view market state computation
         let args = vec![
+            Argument::PlaintextU128(ctx.accounts.market.nonce),
             Argument::Account(
                 ctx.accounts.market.key(),
-                8 + 1 + 32 + 32 + (4 + 200) + 8 + 1 + 8,
-                32 * 4,
+                MARKET_STATE_OFFSET,
+                MARKET_STATE_LEN,
             ),
             Argument::ArcisPubkey(sponsor_pub_key),
         ];
@@ -705,28 +1105,134 @@ pub mod pythia_op {
         Ok(())
     }
 
-    pub fn get_user_position_view(
-        ctx: Context<GetUserPositionView>,
+    /// Sponsor view with implied price and private-window activity computed inside MPC
+    pub fn get_sponsor_market_summary(
+        ctx: Context<GetSponsorMarketSummary>,
         computation_offset: u64,
         sponsor_pub_key: [u8; 32],
     ) -> Result<()> {
+        // Sponsor authorization is enforced by the GetSponsorMarketSummary account constraints
         let market = &ctx.accounts.market;
         
-        // Only sponsor can view user positions
         require!(
-            ctx.accounts.sponsor.key() == market.sponsor,
-            ErrorCode::Unauthorized
+            market.has_binary_pools(),
+            ErrorCode::WrongMarketType
         );
+        require!(
+            market.window_state == MarketWindow::Private,
+            ErrorCode::WrongWindowState
+        );
+        charge_sponsor_view(
+            &mut ctx.accounts.market,
+            &ctx.accounts.sponsor,
+            ctx.accounts.sponsor_token_account.as_deref(),
+            ctx.accounts.market_vault.as_deref(),
+            ctx.accounts.usdc_mint.as_deref(),
+            ctx.accounts.token_program.as_ref(),
+        )?;
+        log_sponsor_view(
+            &mut ctx.accounts.sponsor_access_log,
+            ctx.bumps.sponsor_access_log,
+            ctx.accounts.market.key(),
+            ctx.accounts.sponsor.key(),
+            SponsorViewKind::MarketSummary,
+            ctx.accounts.market.key(),
+        )?;
+        let market = &ctx.accounts.market;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        
+        let args = vec![
+            Argument::PlaintextU128(market.nonce),
+            Argument::Account(
+                market.key(),
+                MARKET_STATE_OFFSET,
+                MARKET_STATE_LEN,
+            ),
+            Argument::ArcisPubkey(sponsor_pub_key),
+            Argument::PlaintextU64(market.window_traders as u64),
+        ];
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ViewMarketSummaryCallback::callback_ix(&[])],
+            1,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "view_market_summary")]
+    pub fn view_market_summary_callback(
+        ctx: Context<ViewMarketSummaryCallback>,
+        output: ComputationOutputs<ViewMarketSummaryOutput>,
+    ) -> Result<()> {
+        let o = match output {
+            ComputationOutputs::Success(ViewMarketSummaryOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        emit!(SponsorMarketSummaryEvent {
+            market: ctx.accounts.market.key(),
+            encrypted_summary: o.ciphertexts,
+            nonce: o.nonce,
+        });
+        
+        Ok(())
+    }
 
+    pub fn get_user_position_view(
+        ctx: Context<GetUserPositionView>,
+        computation_offset: u64,
+        sponsor_pub_key: [u8; 32],
+    ) -> Result<()> {
+        // Sponsor authorization is enforced by the GetUserPositionView account constraints
+        // Open positions can be empty sybils, so the threshold is checked against the
+        // staked count in the current aggregation round, inside the circuit
+        let market = &ctx.accounts.market;
+        let position_aggregate = &ctx.accounts.position_aggregate;
+        require!(
+            position_aggregate.positions_included >= market.min_view_traders.max(1),
+            ErrorCode::NotEnoughTraders
+        );
+        charge_sponsor_view(
+            &mut ctx.accounts.market,
+            &ctx.accounts.sponsor,
+            ctx.accounts.sponsor_token_account.as_deref(),
+            ctx.accounts.market_vault.as_deref(),
+            ctx.accounts.usdc_mint.as_deref(),
+            ctx.accounts.token_program.as_ref(),
+        )?;
+        log_sponsor_view(
+            &mut ctx.accounts.sponsor_access_log,
+            ctx.bumps.sponsor_access_log,
+            ctx.accounts.market.key(),
+            ctx.accounts.sponsor.key(),
+            SponsorViewKind::Position,
+            ctx.accounts.user_position.key(),
+        )?;
+        let market = &ctx.accounts.market;
+        let position_aggregate = &ctx.accounts.position_aggregate;
         ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
         
         // Queue the view user position computation
         let args = vec![
+            Argument::PlaintextU128(ctx.accounts.user_position.nonce),
             Argument::Account(
                 ctx.accounts.user_position.key(),
-                8 + 1 + 32 + 32,
-                32 * 2,
+                USER_POSITION_STATE_OFFSET,
+                USER_POSITION_STATE_LEN, // yes_tokens, no_tokens, yes_tokens_closed, no_tokens_closed
+            ),
+            Argument::PlaintextU128(position_aggregate.nonce),
+            Argument::Account(
+                position_aggregate.key(),
+                POSITION_AGGREGATE_STATE_OFFSET,
+                POSITION_AGGREGATE_STATE_LEN,
             ),
+            Argument::PlaintextU64(market.min_view_traders as u64),
             Argument::ArcisPubkey(sponsor_pub_key),
         ];
 
@@ -761,101 +1267,5915 @@ pub mod pythia_op {
         Ok(())
     }
 
-    pub fn resolve_market(
-        ctx: Context<ResolveMarket>,
-        outcome: bool,
+    /// Re-encrypts the caller's own position to their x25519 key
+    pub fn view_my_position(
+        ctx: Context<ViewMyPosition>,
+        computation_offset: u64,
+        user_pub_key: [u8; 32],
     ) -> Result<()> {
-        let market = &mut ctx.accounts.market;
-        let clock = Clock::get()?;
-        
-        // Only authority can resolve
-        require!(
-            ctx.accounts.authority.key() == market.authority,
-            ErrorCode::Unauthorized
-        );
-        
-        // Must be past resolution date
-        require!(
-            clock.unix_timestamp >= market.resolution_date,
-            ErrorCode::NotYetResolvable
-        );
-        
-        market.resolved = true;
-        market.outcome = Some(outcome);
+        // Ownership is enforced by the ViewMyPosition account constraints
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
         
-        emit!(MarketResolvedEvent {
-            market: market.key(),
-            outcome,
+        let args = vec![
+            Argument::PlaintextU128(ctx.accounts.user_position.nonce),
+            Argument::Account(
+                ctx.accounts.user_position.key(),
+                USER_POSITION_STATE_OFFSET,
+                USER_POSITION_STATE_LEN,
+            ),
+            Argument::ArcisPubkey(user_pub_key),
+        ];
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ViewMyPositionCallback::callback_ix(&[])],
+            1,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "view_my_position")]
+    pub fn view_my_position_callback(
+        ctx: Context<ViewMyPositionCallback>,
+        output: ComputationOutputs<ViewMyPositionOutput>,
+    ) -> Result<()> {
+        let o = match output {
+            ComputationOutputs::Success(ViewMyPositionOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        emit!(MyPositionViewEvent {
+            user_position: ctx.accounts.user_position.key(),
+            user: ctx.accounts.user_position.user,
+            encrypted_state: o.ciphertexts,
+            nonce: o.nonce,
         });
         
         Ok(())
     }
 
-    // pub fn claim_payout(
-    //     _ctx: Context<ClaimPayout>,
-    // ) -> Result<()> {
-    //     Ok(())
-    // }
-}
-
+    #[allow(clippy::too_many_arguments)]
+    pub fn init_categorical_market(
+        mut ctx: Context<InitCategoricalMarket>,
+        question: String,
+        resolution_date: i64,
+        liquidity_cap: u64,
+        initial_liquidity_usdc: u64,
+        num_outcomes: u8,
+        opp_window_duration: u64,
+        pub_window_duration: u64,
+        max_views_per_window: u32,
+        view_fee_usdc: u64,
+        min_view_traders: u32,
+        window_schedule: Vec<WindowScheduleEntry>,
+    ) -> Result<()> {
+        require!(
+            num_outcomes >= 2 && num_outcomes as usize <= MAX_OUTCOMES,
+            ErrorCode::InvalidOutcomeCount
+        );
+        let accounts = &mut ctx.accounts;
+        authorize_market_creation(
+            &accounts.sponsor,
+            &accounts.sponsor_account,
+            &accounts.sponsor_bond,
+            &accounts.config,
+            accounts.pending_action.as_mut(),
+            &question,
+        )?;
 
-#[derive(Accounts)]
-#[instruction(name: String)]
-pub struct InitSponsor<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + 1 + 32 + (4 + 100) + 1 + 8 + 8,
-        seeds = [b"sponsor", authority.key().as_ref()],
-        bump
-    )]
-    pub sponsor: Account<'info, Sponsor>,
-    
-    pub system_program: Program<'info, System>,
-}
+        // Binary state stays unused, pools live in the categorical state account
+        init_market_common(
+            &mut accounts.market,
+            ctx.bumps.market,
+            &mut accounts.sponsor_account,
+            accounts.sponsor.key(),
+            MarketType::Categorical,
+            MarketParams {
+                question,
+                resolution_date,
+                liquidity_cap,
+                initial_liquidity_usdc,
+                opp_window_duration,
+                pub_window_duration,
+                max_views_per_window,
+                view_fee_usdc,
+                min_view_traders,
+                window_schedule,
+            },
+        )?;
+        accounts.market.num_outcomes = num_outcomes;
 
-#[derive(Accounts)]
-pub struct WhitelistSponsor<'info> {
-    #[account(mut)]
-    pub admin: Signer<'info>,  // TODO: Add proper admin authority check
-    
-    #[account(mut)]
-    pub sponsor: Account<'info, Sponsor>,
-}
+        let categorical_state = &mut accounts.categorical_state;
+        categorical_state.bump = ctx.bumps.categorical_state;
+        categorical_state.market = accounts.market.key();
+        categorical_state.state = [[0; 32]; CATEGORICAL_STATE_ENCRYPTED_FIELDS];
+        categorical_state.nonce = 0;
 
+        Ok(())
+    }
+
+    pub fn init_categorical_market_encrypted(
+        ctx: Context<InitCategoricalMarketEncrypted>,
+        computation_offset: u64,
+        mxe_nonce: u128,
+    ) -> Result<()> {
+        use arcium_client::idl::arcium::types::CallbackAccount;
+        
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        
+        let args = vec![
+            Argument::PlaintextU128(mxe_nonce),
+            Argument::PlaintextU64(ctx.accounts.market.initial_liquidity_usdc),
+            Argument::PlaintextU8(ctx.accounts.market.num_outcomes),
+        ];
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![InitializeCategoricalMarketCallback::callback_ix(&[CallbackAccount {
+                pubkey: ctx.accounts.categorical_state.key(),
+                is_writable: true,
+            }])],
+            1,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "initialize_categorical_market")]
+    pub fn initialize_categorical_market_callback(
+        ctx: Context<InitializeCategoricalMarketCallback>,
+        output: ComputationOutputs<InitializeCategoricalMarketOutput>,
+    ) -> Result<()> {
+        let o = match output {
+            ComputationOutputs::Success(InitializeCategoricalMarketOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+        ctx.accounts.categorical_state.state = o.ciphertexts;
+        ctx.accounts.categorical_state.nonce = o.nonce;
+        Ok(())
+    }
+
+    pub fn trade_categorical_private(
+        ctx: Context<TradeCategoricalPrivate>,
+        computation_offset: u64,
+        amount_ciphertext: [u8; 32],
+        outcome_ciphertext: [u8; 32],
+        trade_pub_key: [u8; 32],
+        trade_nonce: u128,
+    ) -> Result<()> {
+        use arcium_client::idl::arcium::types::CallbackAccount;
+        
+        require_categorical_trading(&ctx.accounts.market)?;
+
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        
+        // Pools and position are updated by one computation, so a trade either lands on both or neither
+        let args = vec![
+            Argument::PlaintextU128(ctx.accounts.categorical_state.nonce),
+            Argument::Account(
+                ctx.accounts.categorical_state.key(),
+                CATEGORICAL_STATE_OFFSET,
+                CATEGORICAL_STATE_LEN, // pools[0..MAX_OUTCOMES], total_trades
+            ),
+            Argument::PlaintextU128(ctx.accounts.categorical_position.nonce),
+            Argument::Account(
+                ctx.accounts.categorical_position.key(),
+                CATEGORICAL_POSITION_STATE_OFFSET,
+                CATEGORICAL_POSITION_STATE_LEN, // tokens[0..MAX_OUTCOMES], net_usdc
+            ),
+            Argument::ArcisPubkey(trade_pub_key),
+            Argument::PlaintextU128(trade_nonce),
+            Argument::EncryptedU64(amount_ciphertext),
+            Argument::EncryptedU8(outcome_ciphertext),
+            Argument::PlaintextU8(ctx.accounts.market.num_outcomes),
+            Argument::PlaintextU64(ctx.accounts.market.state_version),
+            Argument::PlaintextU64(ctx.accounts.categorical_position.state_version),
+        ];
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ProcessCategoricalTradeCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: ctx.accounts.categorical_state.key(),
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: ctx.accounts.market.key(),
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: ctx.accounts.categorical_position.key(),
+                    is_writable: true,
+                },
+            ])],
+            1,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "process_categorical_trade")]
+    pub fn process_categorical_trade_callback(
+        ctx: Context<ProcessCategoricalTradeCallback>,
+        output: ComputationOutputs<ProcessCategoricalTradeOutput>,
+    ) -> Result<()> {
+        let o = match output {
+            ComputationOutputs::Success(ProcessCategoricalTradeOutput { field_0 }) => field_0,
+            _ => {
+                return record_categorical_trade_failure(
+                    &mut ctx.accounts.market,
+                    &mut ctx.accounts.categorical_position,
+                );
+            }
+        };
+        // Either side being stale drops the whole trade; it can be resubmitted
+        if o.field_2 != ctx.accounts.market.state_version
+            || !advance_categorical_position_version(&mut ctx.accounts.categorical_position, o.field_3)
+        {
+            return record_categorical_trade_failure(
+                &mut ctx.accounts.market,
+                &mut ctx.accounts.categorical_position,
+            );
+        }
+        ctx.accounts.market.state_version += 1;
+        ctx.accounts.categorical_state.state = o.field_0.ciphertexts;
+        ctx.accounts.categorical_state.nonce = o.field_0.nonce;
+        ctx.accounts.categorical_position.position_state = o.field_1.ciphertexts;
+        ctx.accounts.categorical_position.nonce = o.field_1.nonce;
+        
+        emit!(TradeEvent {
+            market: ctx.accounts.categorical_state.market,
+            window: MarketWindow::Private,
+        });
+        
+        Ok(())
+    }
+
+    pub fn init_categorical_position(
+        ctx: Context<InitCategoricalPosition>,
+        computation_offset: u64,
+        mxe_nonce: u128,
+    ) -> Result<()> {
+        use arcium_client::idl::arcium::types::CallbackAccount;
+        
+        require!(
+            ctx.accounts.market.market_type == MarketType::Categorical,
+            ErrorCode::WrongMarketType
+        );
+        
+        ctx.accounts.categorical_position.bump = ctx.bumps.categorical_position;
+        ctx.accounts.categorical_position.user = ctx.accounts.user.key();
+        ctx.accounts.categorical_position.market = ctx.accounts.market.key();
+        ctx.accounts.categorical_position.nonce = 0;
+        ctx.accounts.categorical_position.position_state = [[0; 32]; CATEGORICAL_POSITION_ENCRYPTED_FIELDS];
+        ctx.accounts.categorical_position.refund_usdc = None;
+        ctx.accounts.categorical_position.refund_pending = false;
+        ctx.accounts.categorical_position.failed_updates = 0;
+        ctx.accounts.categorical_position.last_failure_ts = 0;
+        ctx.accounts.categorical_position.state_version = 0;
+        
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        
+        let args = vec![
+            Argument::PlaintextU128(mxe_nonce),
+        ];
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![InitializeCategoricalPositionCallback::callback_ix(&[CallbackAccount {
+                pubkey: ctx.accounts.categorical_position.key(),
+                is_writable: true,
+            }])],
+            1,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "initialize_categorical_position")]
+    pub fn initialize_categorical_position_callback(
+        ctx: Context<InitializeCategoricalPositionCallback>,
+        output: ComputationOutputs<InitializeCategoricalPositionOutput>,
+    ) -> Result<()> {
+        let o = match output {
+            ComputationOutputs::Success(InitializeCategoricalPositionOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+        ctx.accounts.categorical_position.position_state = o.ciphertexts;
+        ctx.accounts.categorical_position.nonce = o.nonce;
+        // Updates queued before the position existed computed from an empty state
+        ctx.accounts.categorical_position.state_version += 1;
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn init_scalar_market(
+        mut ctx: Context<InitMarket>,
+        question: String,
+        resolution_date: i64,
+        liquidity_cap: u64,
+        initial_liquidity_usdc: u64,
+        scalar_lower: u64,
+        scalar_upper: u64,
+        opp_window_duration: u64,
+        pub_window_duration: u64,
+        max_views_per_window: u32,
+        view_fee_usdc: u64,
+        min_view_traders: u32,
+        window_schedule: Vec<WindowScheduleEntry>,
+    ) -> Result<()> {
+        require!(scalar_lower < scalar_upper, ErrorCode::InvalidScalarRange);
+        let accounts = &mut ctx.accounts;
+        authorize_market_creation(
+            &accounts.sponsor,
+            &accounts.sponsor_account,
+            &accounts.sponsor_bond,
+            &accounts.config,
+            accounts.pending_action.as_mut(),
+            &question,
+        )?;
+
+        // Long/short tokens use the binary yes/no pools and AMM
+        init_market_common(
+            &mut accounts.market,
+            ctx.bumps.market,
+            &mut accounts.sponsor_account,
+            accounts.sponsor.key(),
+            MarketType::Scalar,
+            MarketParams {
+                question,
+                resolution_date,
+                liquidity_cap,
+                initial_liquidity_usdc,
+                opp_window_duration,
+                pub_window_duration,
+                max_views_per_window,
+                view_fee_usdc,
+                min_view_traders,
+                window_schedule,
+            },
+        )?;
+        accounts.market.scalar_lower = scalar_lower;
+        accounts.market.scalar_upper = scalar_upper;
+
+        Ok(())
+    }
+
+    pub fn init_market_group(
+        ctx: Context<InitMarketGroup>,
+        name: String,
+        max_yes: u8,
+        collateral_usdc: u64,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.sponsor_account.is_whitelisted,
+            ErrorCode::SponsorNotWhitelisted
+        );
+        require!(max_yes > 0, ErrorCode::InvalidGroupConfig);
+
+        transfer_usdc(
+            &ctx.accounts.sponsor_token_account,
+            &ctx.accounts.group_vault,
+            ctx.accounts.sponsor.to_account_info(),
+            &ctx.accounts.usdc_mint,
+            &ctx.accounts.token_program,
+            collateral_usdc,
+            &[],
+        )?;
+
+        let group = &mut ctx.accounts.market_group;
+        group.bump = ctx.bumps.market_group;
+        group.sponsor = ctx.accounts.sponsor_account.key();
+        group.authority = ctx.accounts.sponsor.key();
+        group.name = name;
+        group.max_yes = max_yes;
+        group.collateral_usdc = collateral_usdc;
+        group.max_liquidity_cap = 0;
+        group.markets = Vec::new();
+        group.resolved_yes = 0;
+        group.resolved_count = 0;
+        group.completed = false;
+
+        Ok(())
+    }
+
+    /// Tops up a group's collateral vault, e.g. before adding a market with a larger liquidity cap.
+    pub fn deposit_group_collateral(
+        ctx: Context<DepositGroupCollateral>,
+        amount: u64,
+    ) -> Result<()> {
+        transfer_usdc(
+            &ctx.accounts.authority_token_account,
+            &ctx.accounts.group_vault,
+            ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.usdc_mint,
+            &ctx.accounts.token_program,
+            amount,
+            &[],
+        )?;
+
+        let group = &mut ctx.accounts.market_group;
+        group.collateral_usdc = group.collateral_usdc
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    /// Returns the group collateral once every market in the group has resolved.
+    pub fn withdraw_group_collateral(
+        ctx: Context<WithdrawGroupCollateral>,
+    ) -> Result<()> {
+        let group = &ctx.accounts.market_group;
+        require!(
+            group.resolved_count as usize == group.markets.len(),
+            ErrorCode::GroupNotSettled
+        );
+
+        let amount = ctx.accounts.group_vault.amount;
+        let bump = [group.bump];
+        let seeds: &[&[u8]] = &[
+            b"market_group",
+            group.sponsor.as_ref(),
+            group.name.as_bytes(),
+            &bump,
+        ];
+        transfer_usdc(
+            &ctx.accounts.group_vault,
+            &ctx.accounts.authority_token_account,
+            ctx.accounts.market_group.to_account_info(),
+            &ctx.accounts.usdc_mint,
+            &ctx.accounts.token_program,
+            amount,
+            &[seeds],
+        )?;
+
+        ctx.accounts.market_group.collateral_usdc = 0;
+        Ok(())
+    }
+
+    pub fn add_market_to_group(
+        ctx: Context<AddMarketToGroup>,
+    ) -> Result<()> {
+        let vault_balance = ctx.accounts.group_vault.amount;
+        let market_key = ctx.accounts.market.key();
+        let group_key = ctx.accounts.market_group.key();
+        let market = &mut ctx.accounts.market;
+        let group = &mut ctx.accounts.market_group;
+
+        require!(
+            market.market_type == MarketType::Binary,
+            ErrorCode::WrongMarketType
+        );
+        require!(market.group.is_none(), ErrorCode::MarketInGroup);
+        require!(!market.resolved, ErrorCode::MarketResolved);
+        require!(!group.completed, ErrorCode::GroupCompleted);
+        require!(
+            group.markets.len() < MAX_GROUP_MARKETS,
+            ErrorCode::GroupFull
+        );
+
+        // Up to max_yes markets can pay out, so the USDC held in the group vault must cover
+        // that many markets at the largest liquidity cap in the group
+        let max_liquidity_cap = group.max_liquidity_cap.max(market.liquidity_cap);
+        let required_collateral = max_liquidity_cap
+            .checked_mul(group.max_yes as u64)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(
+            vault_balance >= required_collateral,
+            ErrorCode::InsufficientGroupCollateral
+        );
+
+        group.max_liquidity_cap = max_liquidity_cap;
+        group.markets.push(market_key);
+        market.group = Some(group_key);
+
+        Ok(())
+    }
+
+    /// Permissionless crank: once a group has reached its YES count, every
+    /// remaining market in it resolves NO without waiting for its resolution date.
+    pub fn settle_group_market(
+        ctx: Context<SettleGroupMarket>,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.market_group.completed,
+            ErrorCode::GroupNotCompleted
+        );
+        
+        settle_market(
+            &mut ctx.accounts.market,
+            &mut ctx.accounts.sponsor_account,
+            Some(&mut ctx.accounts.market_group),
+            OUTCOME_NO,
+            0,
+        )
+    }
+
+    /// First step of a two-step authority transfer; the new key must accept.
+    pub fn propose_market_authority(
+        ctx: Context<UpdateMarketAuthority>,
+        new_authority: Pubkey,
+    ) -> Result<()> {
+        let approved = consume_sponsor_approval(
+            &ctx.accounts.sponsor_account,
+            ctx.accounts.authority.key(),
+            ctx.accounts.pending_action.as_mut(),
+            SponsorAction::TransferMarketAuthority {
+                market: ctx.accounts.market.key(),
+                new_authority,
+            },
+        )?;
+        if !approved {
+            require_keys_eq!(
+                ctx.accounts.authority.key(),
+                ctx.accounts.market.authority,
+                ErrorCode::Unauthorized
+            );
+        }
+        
+        ctx.accounts.market.pending_authority = Some(new_authority);
+        Ok(())
+    }
+
+    pub fn accept_market_authority(
+        ctx: Context<AcceptMarketAuthority>,
+    ) -> Result<()> {
+        let market = &mut ctx.accounts.market;
+        let previous_authority = market.authority;
+        
+        market.authority = ctx.accounts.new_authority.key();
+        market.pending_authority = None;
+        
+        emit!(MarketAuthorityTransferredEvent {
+            market: market.key(),
+            previous_authority,
+            new_authority: market.authority,
+        });
+        
+        Ok(())
+    }
+
+    /// Sets or clears the delegated operator key
+    pub fn set_market_operator(
+        ctx: Context<UpdateMarketAuthority>,
+        operator: Option<Pubkey>,
+    ) -> Result<()> {
+        let approved = consume_sponsor_approval(
+            &ctx.accounts.sponsor_account,
+            ctx.accounts.authority.key(),
+            ctx.accounts.pending_action.as_mut(),
+            SponsorAction::SetMarketOperator {
+                market: ctx.accounts.market.key(),
+                operator,
+            },
+        )?;
+        if !approved {
+            require_keys_eq!(
+                ctx.accounts.authority.key(),
+                ctx.accounts.market.authority,
+                ErrorCode::Unauthorized
+            );
+        }
+        
+        ctx.accounts.market.operator = operator;
+        Ok(())
+    }
+
+    /// Raises the number of positions required before the sponsor may view positions.
+    /// Never lowered, so traders keep the anonymity set they entered under.
+    pub fn set_min_view_traders(
+        ctx: Context<UpdateMarketAuthority>,
+        min_view_traders: u32,
+    ) -> Result<()> {
+        let approved = consume_sponsor_approval(
+            &ctx.accounts.sponsor_account,
+            ctx.accounts.authority.key(),
+            ctx.accounts.pending_action.as_mut(),
+            SponsorAction::SetMinViewTraders {
+                market: ctx.accounts.market.key(),
+                min_view_traders,
+            },
+        )?;
+        if !approved {
+            require_keys_eq!(
+                ctx.accounts.authority.key(),
+                ctx.accounts.market.authority,
+                ErrorCode::Unauthorized
+            );
+        }
+        
+        let market = &mut ctx.accounts.market;
+        require!(
+            min_view_traders >= market.min_view_traders,
+            ErrorCode::ViewThresholdDecrease
+        );
+        market.min_view_traders = min_view_traders;
+        Ok(())
+    }
+
+    /// Pays the sponsor view fees collected in a market's vault out to the admin's USDC account.
+    pub fn withdraw_view_fees(ctx: Context<WithdrawViewFees>) -> Result<()> {
+        let amount = ctx.accounts.market.view_fees_collected;
+        require!(amount > 0, ErrorCode::NoViewFees);
+
+        pay_from_market_vault(
+            &ctx.accounts.market,
+            &ctx.accounts.market_vault,
+            &ctx.accounts.recipient_token_account,
+            &ctx.accounts.usdc_mint,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        ctx.accounts.market.view_fees_collected = 0;
+
+        emit!(ViewFeesWithdrawnEvent {
+            market: ctx.accounts.market.key(),
+            recipient: ctx.accounts.recipient_token_account.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Starts optimistic resolution: the market authority bonds a proposed outcome
+    /// (or value for scalar markets) which becomes final unless disputed in time.
+    pub fn propose_resolution(
+        ctx: Context<ProposeResolution>,
+        outcome: u8,
+        value: u64,
+    ) -> Result<()> {
+        let approved = consume_sponsor_approval(
+            &ctx.accounts.sponsor_account,
+            ctx.accounts.authority.key(),
+            ctx.accounts.pending_action.as_mut(),
+            SponsorAction::ResolveMarket {
+                market: ctx.accounts.market.key(),
+                outcome,
+                value,
+            },
+        )?;
+        if !approved {
+            require_keys_eq!(
+                ctx.accounts.authority.key(),
+                ctx.accounts.market.authority,
+                ErrorCode::Unauthorized
+            );
+        }
+        
+        let market = &ctx.accounts.market;
+        let clock = Clock::get()?;
+        
+        require!(!market.resolved, ErrorCode::MarketResolved);
+        require!(
+            clock.unix_timestamp >= market.resolution_date,
+            ErrorCode::NotYetResolvable
+        );
+        if market.market_type != MarketType::Scalar {
+            require!(
+                outcome < market.num_outcomes,
+                ErrorCode::InvalidOutcome
+            );
+        }
+        
+        open_resolution_proposal(ctx.accounts, ctx.bumps.proposal, outcome, value)
+    }
+
+    /// Resolves YES ahead of `resolution_date` once the sponsor has acted on the opportunity.
+    /// Trading freezes immediately and the usual dispute window applies.
+    pub fn early_resolve_yes(
+        ctx: Context<ProposeResolution>,
+    ) -> Result<()> {
+        let approved = consume_sponsor_approval(
+            &ctx.accounts.sponsor_account,
+            ctx.accounts.authority.key(),
+            ctx.accounts.pending_action.as_mut(),
+            SponsorAction::ResolveMarket {
+                market: ctx.accounts.market.key(),
+                outcome: OUTCOME_YES,
+                value: 0,
+            },
+        )?;
+        if !approved {
+            require_keys_eq!(
+                ctx.accounts.authority.key(),
+                ctx.accounts.market.authority,
+                ErrorCode::Unauthorized
+            );
+        }
+        
+        let market = &mut ctx.accounts.market;
+        
+        require!(!market.resolved, ErrorCode::MarketResolved);
+        require!(
+            market.market_type == MarketType::Binary,
+            ErrorCode::WrongMarketType
+        );
+        require!(market.private_window_opened, ErrorCode::PrivateWindowNotStarted);
+        
+        market.trading_frozen = true;
+        
+        open_resolution_proposal(ctx.accounts, ctx.bumps.proposal, OUTCOME_YES, 0)
+    }
+
+    pub fn dispute_resolution(
+        ctx: Context<DisputeResolution>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let proposal = &ctx.accounts.proposal;
+        
+        let holds_position = if ctx.accounts.market.market_type == MarketType::Categorical {
+            ctx.accounts.categorical_position.is_some()
+        } else {
+            ctx.accounts.user_position.is_some()
+        };
+        require!(holds_position, ErrorCode::PositionRequired);
+        require!(proposal.disputer.is_none(), ErrorCode::AlreadyDisputed);
+        require!(
+            clock.unix_timestamp < proposal.dispute_deadline,
+            ErrorCode::DisputeWindowClosed
+        );
+        
+        // Counter-bond matches the proposer's bond; the winner of the dispute takes both
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.disputer.to_account_info(),
+                    to: ctx.accounts.proposal.to_account_info(),
+                },
+            ),
+            proposal.bond,
+        )?;
+        
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.disputer = Some(ctx.accounts.disputer.key());
+        
+        ctx.accounts.sponsor_account.resolutions_disputed += 1;
+        emit_sponsor_stats(&ctx.accounts.sponsor_account);
+        
+        emit!(ResolutionDisputedEvent {
+            market: proposal.market,
+            disputer: ctx.accounts.disputer.key(),
+        });
+        
+        Ok(())
+    }
+
+    /// Permissionless: settles an undisputed proposal once the dispute window has passed
+    /// and returns the bond to the proposer.
+    pub fn finalize_resolution(
+        ctx: Context<FinalizeResolution>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let proposal = &ctx.accounts.proposal;
+        
+        require!(proposal.disputer.is_none(), ErrorCode::AlreadyDisputed);
+        require!(
+            clock.unix_timestamp >= proposal.dispute_deadline,
+            ErrorCode::DisputeWindowOpen
+        );
+        
+        // A grouped market may already have been settled NO by its group; the proposal is then void
+        if !ctx.accounts.market.resolved {
+            settle_market(
+                &mut ctx.accounts.market,
+                &mut ctx.accounts.sponsor_account,
+                ctx.accounts.market_group.as_mut(),
+                proposal.outcome,
+                proposal.value,
+            )?;
+        }
+        
+        ctx.accounts.proposal.close(ctx.accounts.proposer.to_account_info())?;
+        
+        Ok(())
+    }
+
+    /// Admin ruling on a disputed proposal. The side that was right receives both bonds.
+    pub fn resolve_dispute(
+        ctx: Context<ResolveDispute>,
+        outcome: u8,
+        value: u64,
+    ) -> Result<()> {
+        let proposal = &ctx.accounts.proposal;
+        require!(proposal.disputer.is_some(), ErrorCode::NotDisputed);
+        
+        let upheld = if ctx.accounts.market.market_type == MarketType::Scalar {
+            value == proposal.value
+        } else {
+            outcome == proposal.outcome
+        };
+        
+        if ctx.accounts.market.resolved {
+            // Settled by its group in the meantime: refund the counter-bond, rest goes back to the proposer
+            let bond = proposal.bond;
+            **ctx.accounts.proposal.to_account_info().try_borrow_mut_lamports()? -= bond;
+            **ctx.accounts.disputer.try_borrow_mut_lamports()? += bond;
+            ctx.accounts.proposal.close(ctx.accounts.proposer.to_account_info())?;
+            return Ok(());
+        }
+        
+        settle_market(
+            &mut ctx.accounts.market,
+            &mut ctx.accounts.sponsor_account,
+            ctx.accounts.market_group.as_mut(),
+            outcome,
+            value,
+        )?;
+        
+        if !upheld {
+            ctx.accounts.sponsor_account.resolutions_overturned += 1;
+            emit_sponsor_stats(&ctx.accounts.sponsor_account);
+        }
+        
+        let winner = if upheld {
+            ctx.accounts.proposer.to_account_info()
+        } else {
+            ctx.accounts.disputer.to_account_info()
+        };
+        ctx.accounts.proposal.close(winner)?;
+        
+        Ok(())
+    }
+
+    /// Resolves a market as invalid (e.g. the startup shut down before the sponsor could decide).
+    /// Callable by the admin or the market authority until the market is resolved.
+    pub fn mark_market_invalid(
+        ctx: Context<MarkMarketInvalid>,
+    ) -> Result<()> {
+        // The admin acts alone; the market side needs the sponsor's approval like any resolution
+        if ctx.accounts.authority.key() != ctx.accounts.config.admin {
+            let approved = consume_sponsor_approval(
+                &ctx.accounts.sponsor_account,
+                ctx.accounts.authority.key(),
+                ctx.accounts.pending_action.as_mut(),
+                SponsorAction::MarkMarketInvalid {
+                    market: ctx.accounts.market.key(),
+                },
+            )?;
+            if !approved {
+                require_keys_eq!(
+                    ctx.accounts.authority.key(),
+                    ctx.accounts.market.authority,
+                    ErrorCode::Unauthorized
+                );
+            }
+        }
+        
+        let market = &mut ctx.accounts.market;
+        
+        require!(!market.resolved, ErrorCode::MarketResolved);
+        
+        if let Some(group_key) = market.group {
+            let group = ctx.accounts.market_group.as_mut().ok_or(ErrorCode::MarketNotInGroup)?;
+            require_keys_eq!(group.key(), group_key, ErrorCode::MarketNotInGroup);
+            group.resolved_count += 1;
+        }
+        
+        market.resolved = true;
+        market.invalid = true;
+        market.outcome = None;
+        
+        emit!(MarketInvalidatedEvent {
+            market: market.key(),
+        });
+        
+        record_sponsor_resolution(&mut ctx.accounts.sponsor_account, market)
+    }
+
+    /// Creates the market's USDC vault, which pays out invalid-market refunds. Permissionless.
+    pub fn init_market_vault(_ctx: Context<InitMarketVault>) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn claim_invalid_refund(
+        ctx: Context<ClaimInvalidRefund>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        use arcium_client::idl::arcium::types::CallbackAccount;
+        
+        require!(ctx.accounts.market.invalid, ErrorCode::MarketNotInvalid);
+        // A queued claim counts as claimed, so a second one can't be queued behind it
+        let user_position = &mut ctx.accounts.user_position;
+        require!(
+            user_position.refund_usdc.is_none() && !user_position.refund_pending,
+            ErrorCode::RefundAlreadyComputed
+        );
+        user_position.refund_pending = true;
+        
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        
+        let args = vec![
+            Argument::PlaintextU128(ctx.accounts.user_position.nonce),
+            Argument::Account(
+                ctx.accounts.user_position.key(),
+                USER_POSITION_STATE_OFFSET,
+                USER_POSITION_STATE_LEN,
+            ),
+        ];
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![RevealRefundCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: ctx.accounts.user_position.key(),
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: ctx.accounts.market.key(),
+                    is_writable: false,
+                },
+                CallbackAccount {
+                    pubkey: ctx.accounts.market_vault.key(),
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: ctx.accounts.user_token_account.key(),
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: ctx.accounts.usdc_mint.key(),
+                    is_writable: false,
+                },
+                CallbackAccount {
+                    pubkey: ctx.accounts.token_program.key(),
+                    is_writable: false,
+                },
+            ])],
+            1,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "reveal_refund")]
+    pub fn reveal_refund_callback(
+        ctx: Context<RevealRefundCallback>,
+        output: ComputationOutputs<RevealRefundOutput>,
+    ) -> Result<()> {
+        ctx.accounts.user_position.refund_pending = false;
+        let usdc_amount = match output {
+            ComputationOutputs::Success(RevealRefundOutput { field_0 }) => field_0,
+            // refund_usdc stays unset, so the refund can be claimed again
+            _ => {
+                return record_position_failure(
+                    &mut ctx.accounts.user_position,
+                    ComputationKind::Refund,
+                );
+            }
+        };
+        if ctx.accounts.user_position.refund_usdc.is_some()
+            || ctx.accounts.market_vault.amount < usdc_amount
+        {
+            // Nothing is paid; an underfunded vault can be topped up and the refund claimed again
+            return record_position_failure(
+                &mut ctx.accounts.user_position,
+                ComputationKind::Refund,
+            );
+        }
+        
+        pay_from_market_vault(
+            &ctx.accounts.market,
+            &ctx.accounts.market_vault,
+            &ctx.accounts.user_token_account,
+            &ctx.accounts.usdc_mint,
+            &ctx.accounts.token_program,
+            usdc_amount,
+        )?;
+        let user_position = &mut ctx.accounts.user_position;
+        user_position.refund_usdc = Some(usdc_amount);
+        
+        emit!(RefundEvent {
+            market: user_position.market,
+            user: user_position.user,
+        });
+        
+        Ok(())
+    }
+
+    pub fn claim_categorical_invalid_refund(
+        ctx: Context<ClaimCategoricalInvalidRefund>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        use arcium_client::idl::arcium::types::CallbackAccount;
+        
+        require!(ctx.accounts.market.invalid, ErrorCode::MarketNotInvalid);
+        // A queued claim counts as claimed, so a second one can't be queued behind it
+        let categorical_position = &mut ctx.accounts.categorical_position;
+        require!(
+            categorical_position.refund_usdc.is_none() && !categorical_position.refund_pending,
+            ErrorCode::RefundAlreadyComputed
+        );
+        categorical_position.refund_pending = true;
+        
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        
+        let args = vec![
+            Argument::PlaintextU128(ctx.accounts.categorical_position.nonce),
+            Argument::Account(
+                ctx.accounts.categorical_position.key(),
+                CATEGORICAL_POSITION_STATE_OFFSET,
+                CATEGORICAL_POSITION_STATE_LEN,
+            ),
+        ];
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![RevealCategoricalRefundCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: ctx.accounts.categorical_position.key(),
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: ctx.accounts.market.key(),
+                    is_writable: false,
+                },
+                CallbackAccount {
+                    pubkey: ctx.accounts.market_vault.key(),
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: ctx.accounts.user_token_account.key(),
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: ctx.accounts.usdc_mint.key(),
+                    is_writable: false,
+                },
+                CallbackAccount {
+                    pubkey: ctx.accounts.token_program.key(),
+                    is_writable: false,
+                },
+            ])],
+            1,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "reveal_categorical_refund")]
+    pub fn reveal_categorical_refund_callback(
+        ctx: Context<RevealCategoricalRefundCallback>,
+        output: ComputationOutputs<RevealCategoricalRefundOutput>,
+    ) -> Result<()> {
+        ctx.accounts.categorical_position.refund_pending = false;
+        let usdc_amount = match output {
+            ComputationOutputs::Success(RevealCategoricalRefundOutput { field_0 }) => field_0,
+            // refund_usdc stays unset, so the refund can be claimed again
+            _ => {
+                return record_categorical_position_failure(
+                    &mut ctx.accounts.categorical_position,
+                    ComputationKind::Refund,
+                );
+            }
+        };
+        if ctx.accounts.categorical_position.refund_usdc.is_some()
+            || ctx.accounts.market_vault.amount < usdc_amount
+        {
+            // Nothing is paid; an underfunded vault can be topped up and the refund claimed again
+            return record_categorical_position_failure(
+                &mut ctx.accounts.categorical_position,
+                ComputationKind::Refund,
+            );
+        }
+        
+        pay_from_market_vault(
+            &ctx.accounts.market,
+            &ctx.accounts.market_vault,
+            &ctx.accounts.user_token_account,
+            &ctx.accounts.usdc_mint,
+            &ctx.accounts.token_program,
+            usdc_amount,
+        )?;
+        let categorical_position = &mut ctx.accounts.categorical_position;
+        categorical_position.refund_usdc = Some(usdc_amount);
+        
+        emit!(RefundEvent {
+            market: categorical_position.market,
+            user: categorical_position.user,
+        });
+        
+        Ok(())
+    }
+
+    /// Attaches an encrypted research hash to the caller's latest trade, identified by its
+    /// position trade number
+    #[allow(clippy::too_many_arguments)]
+    pub fn commit_research(
+        ctx: Context<CommitResearch>,
+        computation_offset: u64,
+        trade_id: u64,
+        hash_hi_ciphertext: [u8; 32],
+        hash_lo_ciphertext: [u8; 32],
+        note_pub_key: [u8; 32],
+        note_nonce: u128,
+        mxe_nonce: u128,
+    ) -> Result<()> {
+        use arcium_client::idl::arcium::types::CallbackAccount;
+        
+        require!(!ctx.accounts.market.resolved, ErrorCode::MarketResolved);
+        
+        let user_position = &ctx.accounts.user_position;
+        require!(
+            trade_id != 0 && trade_id == user_position.trade_count,
+            ErrorCode::InvalidTradeId
+        );
+        let committed_ts = user_position.last_trade_ts;
+        // A commitment whose computation failed can be submitted again
+        require!(
+            ctx.accounts.research_commitment.nonce == 0,
+            ErrorCode::ResearchAlreadyCommitted
+        );
+        
+        let research_commitment = &mut ctx.accounts.research_commitment;
+        research_commitment.bump = ctx.bumps.research_commitment;
+        research_commitment.user_position = ctx.accounts.user_position.key();
+        research_commitment.market = ctx.accounts.market.key();
+        research_commitment.user = ctx.accounts.user.key();
+        research_commitment.trade_id = trade_id;
+        research_commitment.committed_ts = committed_ts;
+        research_commitment.note_state = [[0; 32]; RESEARCH_NOTE_ENCRYPTED_FIELDS];
+        research_commitment.nonce = 0;
+        research_commitment.revealed_hash = None;
+        
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        
+        let args = vec![
+            Argument::ArcisPubkey(note_pub_key),
+            Argument::PlaintextU128(note_nonce),
+            Argument::EncryptedU128(hash_hi_ciphertext),
+            Argument::EncryptedU128(hash_lo_ciphertext),
+            Argument::PlaintextU128(mxe_nonce),
+        ];
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![CommitResearchCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: ctx.accounts.research_commitment.key(),
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: ctx.accounts.user_position.key(),
+                    is_writable: true,
+                },
+            ])],
+            1,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "commit_research")]
+    pub fn commit_research_callback(
+        ctx: Context<CommitResearchCallback>,
+        output: ComputationOutputs<CommitResearchOutput>,
+    ) -> Result<()> {
+        let o = match output {
+            ComputationOutputs::Success(CommitResearchOutput { field_0 }) => field_0,
+            _ => {
+                return record_position_failure(
+                    &mut ctx.accounts.user_position,
+                    ComputationKind::Research,
+                );
+            }
+        };
+        ctx.accounts.research_commitment.note_state = o.ciphertexts;
+        ctx.accounts.research_commitment.nonce = o.nonce;
+        Ok(())
+    }
+
+    pub fn get_research_view(
+        ctx: Context<GetResearchView>,
+        computation_offset: u64,
+        sponsor_pub_key: [u8; 32],
+    ) -> Result<()> {
+        // Sponsor authorization is enforced by the GetResearchView account constraints
+        charge_sponsor_view(
+            &mut ctx.accounts.market,
+            &ctx.accounts.sponsor,
+            ctx.accounts.sponsor_token_account.as_deref(),
+            ctx.accounts.market_vault.as_deref(),
+            ctx.accounts.usdc_mint.as_deref(),
+            ctx.accounts.token_program.as_ref(),
+        )?;
+        log_sponsor_view(
+            &mut ctx.accounts.sponsor_access_log,
+            ctx.bumps.sponsor_access_log,
+            ctx.accounts.market.key(),
+            ctx.accounts.sponsor.key(),
+            SponsorViewKind::Research,
+            ctx.accounts.research_commitment.key(),
+        )?;
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        
+        let args = vec![
+            Argument::PlaintextU128(ctx.accounts.research_commitment.nonce),
+            Argument::Account(
+                ctx.accounts.research_commitment.key(),
+                RESEARCH_NOTE_STATE_OFFSET,
+                RESEARCH_NOTE_STATE_LEN,
+            ),
+            Argument::ArcisPubkey(sponsor_pub_key),
+        ];
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ViewResearchCallback::callback_ix(&[])],
+            1,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "view_research")]
+    pub fn view_research_callback(
+        ctx: Context<ViewResearchCallback>,
+        output: ComputationOutputs<ViewResearchOutput>,
+    ) -> Result<()> {
+        let o = match output {
+            ComputationOutputs::Success(ViewResearchOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        emit!(ResearchViewEvent {
+            research_commitment: ctx.accounts.research_commitment.key(),
+            encrypted_note: o.ciphertexts,
+            nonce: o.nonce,
+        });
+        
+        Ok(())
+    }
+
+    /// Permissionless: publishes a research hash once its market has resolved
+    pub fn reveal_research(
+        ctx: Context<RevealResearch>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        use arcium_client::idl::arcium::types::CallbackAccount;
+        
+        require!(ctx.accounts.market.resolved, ErrorCode::MarketNotResolved);
+        require!(
+            ctx.accounts.research_commitment.revealed_hash.is_none(),
+            ErrorCode::ResearchAlreadyRevealed
+        );
+        
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        
+        let args = vec![
+            Argument::PlaintextU128(ctx.accounts.research_commitment.nonce),
+            Argument::Account(
+                ctx.accounts.research_commitment.key(),
+                RESEARCH_NOTE_STATE_OFFSET,
+                RESEARCH_NOTE_STATE_LEN,
+            ),
+        ];
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![RevealResearchCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: ctx.accounts.research_commitment.key(),
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: ctx.accounts.market.key(),
+                    is_writable: true,
+                },
+            ])],
+            1,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "reveal_research")]
+    pub fn reveal_research_callback(
+        ctx: Context<RevealResearchCallback>,
+        output: ComputationOutputs<RevealResearchOutput>,
+    ) -> Result<()> {
+        let o = match output {
+            ComputationOutputs::Success(RevealResearchOutput { field_0 }) => field_0,
+            // The hash stays unrevealed, so the crank can retry it
+            _ => {
+                return record_market_failure(
+                    &mut ctx.accounts.market,
+                    ComputationKind::Research,
+                );
+            }
+        };
+        
+        let research_commitment = &mut ctx.accounts.research_commitment;
+        require!(
+            research_commitment.revealed_hash.is_none(),
+            ErrorCode::ResearchAlreadyRevealed
+        );
+        
+        let mut content_hash = [0u8; 32];
+        content_hash[..16].copy_from_slice(&o.field_0.to_be_bytes());
+        content_hash[16..].copy_from_slice(&o.field_1.to_be_bytes());
+        research_commitment.revealed_hash = Some(content_hash);
+        
+        emit!(ResearchRevealedEvent {
+            research_commitment: research_commitment.key(),
+            market: research_commitment.market,
+            user: research_commitment.user,
+            trade_id: research_commitment.trade_id,
+            committed_ts: research_commitment.committed_ts,
+            content_hash,
+        });
+        
+        Ok(())
+    }
+
+    /// Permissionless: adds a position in a resolved binary market to its trader's profile
+    pub fn score_position(
+        ctx: Context<ScorePosition>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        use arcium_client::idl::arcium::types::CallbackAccount;
+        
+        let market = &ctx.accounts.market;
+        require!(market.resolved, ErrorCode::MarketNotResolved);
+        require!(
+            market.market_type == MarketType::Binary && !market.invalid,
+            ErrorCode::WrongMarketType
+        );
+        require!(!ctx.accounts.user_position.scored, ErrorCode::PositionAlreadyScored);
+        let resolved_yes = market.outcome == Some(OUTCOME_YES);
+        
+        let trader_profile = &mut ctx.accounts.trader_profile;
+        if trader_profile.wallet == Pubkey::default() {
+            trader_profile.bump = ctx.bumps.trader_profile;
+            trader_profile.wallet = ctx.accounts.user_position.user;
+        }
+        
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        
+        let args = vec![
+            Argument::PlaintextU128(ctx.accounts.user_position.nonce),
+            Argument::Account(
+                ctx.accounts.user_position.key(),
+                USER_POSITION_STATE_OFFSET,
+                USER_POSITION_STATE_LEN,
+            ),
+            Argument::PlaintextBool(resolved_yes),
+        ];
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ScorePositionCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: ctx.accounts.user_position.key(),
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: ctx.accounts.trader_profile.key(),
+                    is_writable: true,
+                },
+            ])],
+            1,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "score_position")]
+    pub fn score_position_callback(
+        ctx: Context<ScorePositionCallback>,
+        output: ComputationOutputs<ScorePositionOutput>,
+    ) -> Result<()> {
+        let o = match output {
+            ComputationOutputs::Success(ScorePositionOutput { field_0 }) => field_0,
+            // The position stays unscored, so the crank can retry it
+            _ => {
+                return record_position_failure(
+                    &mut ctx.accounts.user_position,
+                    ComputationKind::Score,
+                );
+            }
+        };
+        let (staked_usdc, payout_usdc, entry_price, brier) =
+            (o.field_0, o.field_1, o.field_2, o.field_3);
+        
+        let user_position = &mut ctx.accounts.user_position;
+        require!(!user_position.scored, ErrorCode::PositionAlreadyScored);
+        user_position.scored = true;
+        
+        let trader_profile = &mut ctx.accounts.trader_profile;
+        trader_profile
+            .record_score(staked_usdc, payout_usdc, brier)
+            .ok_or(ErrorCode::Overflow)?;
+        
+        emit!(TraderScoredEvent {
+            trader: trader_profile.wallet,
+            market: user_position.market,
+            staked_usdc,
+            payout_usdc,
+            entry_price,
+            brier,
+            average_brier: trader_profile.average_brier(),
+            realized_pnl: trader_profile.realized_pnl,
+        });
+        
+        Ok(())
+    }
+
+    /// Permissionless crank: reveals how busy the current private window is, without prices
+    pub fn reveal_attention_hint(
+        ctx: Context<RevealAttentionHint>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        use arcium_client::idl::arcium::types::CallbackAccount;
+        
+        let market = &ctx.accounts.market;
+        let clock = Clock::get()?;
+        
+        require!(
+            market.has_binary_pools(),
+            ErrorCode::WrongMarketType
+        );
+        require!(
+            market.window_state == MarketWindow::Private,
+            ErrorCode::WrongWindowState
+        );
+        require!(market.nonce != 0, ErrorCode::PrivateWindowNotStarted);
+        require!(
+            market.attention_hint_due(clock.unix_timestamp),
+            ErrorCode::HintTooSoon
+        );
+        
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        
+        let args = vec![
+            Argument::PlaintextU128(market.nonce),
+            Argument::Account(
+                market.key(),
+                MARKET_STATE_OFFSET,
+                MARKET_STATE_LEN,
+            ),
+            Argument::PlaintextU64(market.initial_liquidity_usdc),
+        ];
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![RevealAttentionHintCallback::callback_ix(&[CallbackAccount {
+                pubkey: ctx.accounts.market.key(),
+                is_writable: true,
+            }])],
+            1,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "reveal_attention_hint")]
+    pub fn reveal_attention_hint_callback(
+        ctx: Context<RevealAttentionHintCallback>,
+        output: ComputationOutputs<RevealAttentionHintOutput>,
+    ) -> Result<()> {
+        let level = match output {
+            ComputationOutputs::Success(RevealAttentionHintOutput { field_0 }) => field_0,
+            // last_hint_ts is unchanged, so the crank can retry right away
+            _ => {
+                return record_market_failure(
+                    &mut ctx.accounts.market,
+                    ComputationKind::AttentionHint,
+                );
+            }
+        };
+        let level = AttentionLevel::from_bucket(level);
+        
+        let clock = Clock::get()?;
+        let market = &mut ctx.accounts.market;
+        market.attention_hint = Some(level);
+        market.last_hint_ts = clock.unix_timestamp;
+        
+        emit!(AttentionHintEvent {
+            market: market.key(),
+            level,
+        });
+        
+        Ok(())
+    }
+
+    /// Opens the order batch for the market's current epoch
+    pub fn init_order_batch(
+        ctx: Context<InitOrderBatch>,
+        computation_offset: u64,
+        mxe_nonce: u128,
+    ) -> Result<()> {
+        use arcium_client::idl::arcium::types::CallbackAccount;
+        
+        let market = &ctx.accounts.market;
+        require!(market.has_binary_pools(), ErrorCode::WrongMarketType);
+        require!(
+            market.window_state == MarketWindow::Private,
+            ErrorCode::WrongWindowState
+        );
+        
+        let clock = Clock::get()?;
+        let order_batch = &mut ctx.accounts.order_batch;
+        order_batch.bump = ctx.bumps.order_batch;
+        order_batch.market = market.key();
+        order_batch.epoch = market.batch_epoch;
+        order_batch.opened_ts = clock.unix_timestamp;
+        order_batch.order_count = 0;
+        order_batch.filled_count = 0;
+        order_batch.cleared = false;
+        order_batch.totals_state = [[0; 32]; BATCH_ENCRYPTED_FIELDS];
+        order_batch.totals_nonce = 0;
+        order_batch.prices_state = [[0; 32]; BATCH_ENCRYPTED_FIELDS];
+        order_batch.prices_nonce = 0;
+        order_batch.clearing = false;
+        
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        
+        let args = vec![
+            Argument::PlaintextU128(mxe_nonce),
+        ];
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![InitializeOrderBatchCallback::callback_ix(&[CallbackAccount {
+                pubkey: ctx.accounts.order_batch.key(),
+                is_writable: true,
+            }])],
+            1,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "initialize_order_batch")]
+    pub fn initialize_order_batch_callback(
+        ctx: Context<InitializeOrderBatchCallback>,
+        output: ComputationOutputs<InitializeOrderBatchOutput>,
+    ) -> Result<()> {
+        let o = match output {
+            ComputationOutputs::Success(InitializeOrderBatchOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+        ctx.accounts.order_batch.totals_state = o.ciphertexts;
+        ctx.accounts.order_batch.totals_nonce = o.nonce;
+        Ok(())
+    }
+
+    /// Adds an encrypted private-window order to the current batch, the only way to trade while private
+    pub fn submit_batch_order(
+        ctx: Context<SubmitBatchOrder>,
+        computation_offset: u64,
+        usdc_amount_ciphertext: [u8; 32],
+        is_buy_yes_ciphertext: [u8; 32],
+        order_pub_key: [u8; 32],
+        order_nonce: u128,
+    ) -> Result<()> {
+        use arcium_client::idl::arcium::types::CallbackAccount;
+        
+        let market = &ctx.accounts.market;
+        require!(market.has_binary_pools(), ErrorCode::WrongMarketType);
+        require!(!market.trading_frozen, ErrorCode::TradingFrozen);
+        require!(!market.pending_transition, ErrorCode::TransitionPending);
+        require!(
+            market.window_state == MarketWindow::Private,
+            ErrorCode::WrongWindowState
+        );
+        require!(!ctx.accounts.order_batch.cleared, ErrorCode::BatchCleared);
+        require!(!ctx.accounts.order_batch.clearing, ErrorCode::BatchClearPending);
+        require!(ctx.accounts.order_batch.totals_nonce != 0, ErrorCode::BatchNotReady);
+        // An order whose submission failed can be submitted again; one in flight can't
+        require!(
+            ctx.accounts.batch_order.nonce == 0 && !ctx.accounts.batch_order.pending,
+            ErrorCode::OrderAlreadySubmitted
+        );
+        ctx.accounts
+            .market
+            .record_window_trader(&mut ctx.accounts.user_position);
+        
+        let batch_order = &mut ctx.accounts.batch_order;
+        batch_order.bump = ctx.bumps.batch_order;
+        batch_order.order_batch = ctx.accounts.order_batch.key();
+        batch_order.user = ctx.accounts.user.key();
+        batch_order.filled = false;
+        batch_order.order_state = [[0; 32]; TRADE_INPUT_ENCRYPTED_FIELDS];
+        batch_order.nonce = 0;
+        batch_order.pending = true;
+        
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        
+        let args = vec![
+            Argument::ArcisPubkey(order_pub_key),
+            Argument::PlaintextU128(order_nonce),
+            Argument::EncryptedU64(usdc_amount_ciphertext),
+            Argument::EncryptedBool(is_buy_yes_ciphertext),
+            Argument::PlaintextU128(ctx.accounts.order_batch.totals_nonce),
+            Argument::Account(
+                ctx.accounts.order_batch.key(),
+                ORDER_BATCH_TOTALS_OFFSET,
+                ORDER_BATCH_STATE_LEN,
+            ),
+        ];
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![SubmitBatchOrderCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: ctx.accounts.batch_order.key(),
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: ctx.accounts.order_batch.key(),
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: ctx.accounts.user_position.key(),
+                    is_writable: true,
+                },
+            ])],
+            1,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "submit_batch_order")]
+    pub fn submit_batch_order_callback(
+        ctx: Context<SubmitBatchOrderCallback>,
+        output: ComputationOutputs<SubmitBatchOrderOutput>,
+    ) -> Result<()> {
+        ctx.accounts.batch_order.pending = false;
+        let o = match output {
+            ComputationOutputs::Success(SubmitBatchOrderOutput { field_0 }) => field_0,
+            // The batch totals are untouched until success, so only the order is released
+            _ => {
+                return record_position_failure(
+                    &mut ctx.accounts.user_position,
+                    ComputationKind::BatchSubmit,
+                );
+            }
+        };
+        
+        // A clear queued before this landed priced the batch without the order, so it stays
+        // out of the totals and can't be filled
+        if ctx.accounts.order_batch.clearing || ctx.accounts.order_batch.cleared {
+            return Ok(());
+        }
+        
+        ctx.accounts.batch_order.order_state = o.field_0.ciphertexts;
+        ctx.accounts.batch_order.nonce = o.field_0.nonce;
+        
+        let order_batch = &mut ctx.accounts.order_batch;
+        order_batch.totals_state = o.field_1.ciphertexts;
+        order_batch.totals_nonce = o.field_1.nonce;
+        order_batch.order_count += 1;
+        
+        Ok(())
+    }
+
+    /// Permissionless crank: clears the current batch once its epoch has run
+    pub fn clear_order_batch(
+        ctx: Context<ClearOrderBatch>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        use arcium_client::idl::arcium::types::CallbackAccount;
+        
+        let market = &ctx.accounts.market;
+        let order_batch = &ctx.accounts.order_batch;
+        let clock = Clock::get()?;
+        
+        require!(
+            market.window_state == MarketWindow::Private,
+            ErrorCode::WrongWindowState
+        );
+        require!(!market.pending_transition, ErrorCode::TransitionPending);
+        require!(!order_batch.cleared, ErrorCode::BatchCleared);
+        require!(!order_batch.clearing, ErrorCode::BatchClearPending);
+        require!(order_batch.totals_nonce != 0, ErrorCode::BatchNotReady);
+        require!(
+            clock.unix_timestamp >= order_batch.opened_ts + BATCH_EPOCH_DURATION,
+            ErrorCode::BatchEpochOpen
+        );
+        
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        
+        let args = vec![
+            Argument::PlaintextU128(market.nonce),
+            Argument::Account(
+                market.key(),
+                MARKET_STATE_OFFSET,
+                MARKET_STATE_LEN,
+            ),
+            Argument::PlaintextU128(order_batch.totals_nonce),
+            Argument::Account(
+                order_batch.key(),
+                ORDER_BATCH_TOTALS_OFFSET,
+                ORDER_BATCH_STATE_LEN,
+            ),
+            Argument::PlaintextU64(order_batch.order_count as u64),
+            Argument::PlaintextU64(market.state_version),
+        ];
+        // Freezes the totals and order count the clear was queued with
+        ctx.accounts.order_batch.clearing = true;
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ClearOrderBatchCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: ctx.accounts.market.key(),
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: ctx.accounts.order_batch.key(),
+                    is_writable: true,
+                },
+            ])],
+            1,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "clear_order_batch")]
+    pub fn clear_order_batch_callback(
+        ctx: Context<ClearOrderBatchCallback>,
+        output: ComputationOutputs<ClearOrderBatchOutput>,
+    ) -> Result<()> {
+        let o = match output {
+            ComputationOutputs::Success(ClearOrderBatchOutput { field_0 }) => field_0,
+            _ => {
+                ctx.accounts.order_batch.clearing = false;
+                return record_market_failure(
+                    &mut ctx.accounts.market,
+                    ComputationKind::ClearOrderBatch,
+                );
+            }
+        };
+        
+        if !advance_state_version(&mut ctx.accounts.market, o.field_2) {
+            // Priced against an older market state; the batch can be cleared again
+            ctx.accounts.order_batch.clearing = false;
+            return record_market_failure(
+                &mut ctx.accounts.market,
+                ComputationKind::ClearOrderBatch,
+            );
+        }
+        
+        let market = &mut ctx.accounts.market;
+        market.market_state = o.field_0.ciphertexts;
+        market.nonce = o.field_0.nonce;
+        // Later orders go into the next epoch's batch
+        market.batch_epoch += 1;
+        
+        let order_batch = &mut ctx.accounts.order_batch;
+        order_batch.prices_state = o.field_1.ciphertexts;
+        order_batch.prices_nonce = o.field_1.nonce;
+        order_batch.clearing = false;
+        order_batch.cleared = true;
+        
+        emit!(OrderBatchClearedEvent {
+            market: market.key(),
+            epoch: order_batch.epoch,
+            order_count: order_batch.order_count,
+        });
+        
+        Ok(())
+    }
+
+    /// Permissionless crank: writes one trader's fill from a cleared batch to their position
+    pub fn fill_batch_order(
+        ctx: Context<FillBatchOrder>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        use arcium_client::idl::arcium::types::CallbackAccount;
+        
+        require!(ctx.accounts.order_batch.cleared, ErrorCode::BatchNotCleared);
+        // Orders whose submission never landed, or landed after the clear was queued, were not priced
+        require!(ctx.accounts.batch_order.nonce != 0, ErrorCode::OrderNotIncluded);
+        require!(!ctx.accounts.batch_order.filled, ErrorCode::OrderAlreadyFilled);
+        
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        
+        let args = vec![
+            Argument::PlaintextU128(ctx.accounts.user_position.nonce),
+            Argument::Account(
+                ctx.accounts.user_position.key(),
+                USER_POSITION_STATE_OFFSET,
+                USER_POSITION_STATE_LEN,
+            ),
+            Argument::PlaintextU128(ctx.accounts.batch_order.nonce),
+            Argument::Account(
+                ctx.accounts.batch_order.key(),
+                BATCH_ORDER_STATE_OFFSET,
+                BATCH_ORDER_STATE_LEN,
+            ),
+            Argument::PlaintextU128(ctx.accounts.order_batch.prices_nonce),
+            Argument::Account(
+                ctx.accounts.order_batch.key(),
+                ORDER_BATCH_PRICES_OFFSET,
+                ORDER_BATCH_STATE_LEN,
+            ),
+            Argument::PlaintextU64(ctx.accounts.user_position.state_version),
+        ];
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![FillBatchOrderCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: ctx.accounts.user_position.key(),
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: ctx.accounts.batch_order.key(),
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: ctx.accounts.order_batch.key(),
+                    is_writable: true,
+                },
+            ])],
+            1,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "fill_batch_order")]
+    pub fn fill_batch_order_callback(
+        ctx: Context<FillBatchOrderCallback>,
+        output: ComputationOutputs<FillBatchOrderOutput>,
+    ) -> Result<()> {
+        let o = match output {
+            ComputationOutputs::Success(FillBatchOrderOutput { field_0 }) => field_0,
+            _ => {
+                return record_position_failure(
+                    &mut ctx.accounts.user_position,
+                    ComputationKind::BatchFill,
+                );
+            }
+        };
+        
+        require!(!ctx.accounts.batch_order.filled, ErrorCode::OrderAlreadyFilled);
+        if !advance_position_version(&mut ctx.accounts.user_position, o.field_1) {
+            // The order stays unfilled so the fill can be cranked again
+            return record_position_failure(
+                &mut ctx.accounts.user_position,
+                ComputationKind::BatchFill,
+            );
+        }
+        
+        let clock = Clock::get()?;
+        ctx.accounts.user_position.position_state = o.field_0.ciphertexts;
+        ctx.accounts.user_position.nonce = o.field_0.nonce;
+        ctx.accounts.user_position.record_trade(clock.unix_timestamp);
+        ctx.accounts.batch_order.filled = true;
+        ctx.accounts.order_batch.filled_count += 1;
+        
+        Ok(())
+    }
+
+    /// Opens the limit order book for the current private window
+    pub fn init_limit_order_book(
+        ctx: Context<InitLimitOrderBook>,
+        computation_offset: u64,
+        mxe_nonce: u128,
+    ) -> Result<()> {
+        use arcium_client::idl::arcium::types::CallbackAccount;
+        
+        let market = &mut ctx.accounts.market;
+        require!(market.has_binary_pools(), ErrorCode::WrongMarketType);
+        require!(
+            market.window_state == MarketWindow::Private,
+            ErrorCode::WrongWindowState
+        );
+        // A book whose initialization failed is re-initialized in place
+        require!(
+            ctx.accounts.limit_order_book.orders_nonce == 0 && !ctx.accounts.limit_order_book.matched,
+            ErrorCode::LimitOrderBookExists
+        );
+        // Cleared again by the callback if the initialization fails
+        market.open_limit_book = Some(ctx.accounts.limit_order_book.key());
+        
+        let limit_order_book = &mut ctx.accounts.limit_order_book;
+        limit_order_book.bump = ctx.bumps.limit_order_book;
+        limit_order_book.market = market.key();
+        limit_order_book.owners = [Pubkey::default(); MAX_LIMIT_ORDERS];
+        limit_order_book.order_count = 0;
+        limit_order_book.matched = false;
+        limit_order_book.applied = [false; MAX_LIMIT_ORDERS];
+        limit_order_book.orders_state = [[0; 32]; LIMIT_ORDER_BOOK_ENCRYPTED_FIELDS];
+        limit_order_book.orders_nonce = 0;
+        limit_order_book.fills_state = [[0; 32]; LIMIT_FILLS_ENCRYPTED_FIELDS];
+        limit_order_book.fills_nonce = 0;
+        limit_order_book.pending_slot = None;
+        
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        
+        let args = vec![
+            Argument::PlaintextU128(mxe_nonce),
+        ];
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![InitializeLimitOrderBookCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: ctx.accounts.limit_order_book.key(),
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: ctx.accounts.market.key(),
+                    is_writable: true,
+                },
+            ])],
+            1,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "initialize_limit_order_book")]
+    pub fn initialize_limit_order_book_callback(
+        ctx: Context<InitializeLimitOrderBookCallback>,
+        output: ComputationOutputs<InitializeLimitOrderBookOutput>,
+    ) -> Result<()> {
+        let o = match output {
+            ComputationOutputs::Success(InitializeLimitOrderBookOutput { field_0 }) => field_0,
+            _ => {
+                // Frees the market to open the book again and to switch windows
+                if ctx.accounts.market.open_limit_book == Some(ctx.accounts.limit_order_book.key()) {
+                    ctx.accounts.market.open_limit_book = None;
+                }
+                return record_market_failure(
+                    &mut ctx.accounts.market,
+                    ComputationKind::LimitBookInit,
+                );
+            }
+        };
+        ctx.accounts.limit_order_book.orders_state = o.ciphertexts;
+        ctx.accounts.limit_order_book.orders_nonce = o.nonce;
+        Ok(())
+    }
+
+    pub fn place_limit_order(
+        ctx: Context<PlaceLimitOrder>,
+        computation_offset: u64,
+        order_ciphertexts: [[u8; 32]; LIMIT_ORDER_INPUT_ENCRYPTED_FIELDS],
+        order_pub_key: [u8; 32],
+        order_nonce: u128,
+    ) -> Result<()> {
+        use arcium_client::idl::arcium::types::CallbackAccount;
+        
+        let market = &ctx.accounts.market;
+        require!(!market.trading_frozen, ErrorCode::TradingFrozen);
+        require!(
+            market.window_state == MarketWindow::Private,
+            ErrorCode::WrongWindowState
+        );
+        
+        let limit_order_book = &mut ctx.accounts.limit_order_book;
+        require!(!limit_order_book.matched, ErrorCode::LimitOrdersMatched);
+        require!(limit_order_book.orders_nonce != 0, ErrorCode::LimitOrderBookNotReady);
+        require!(limit_order_book.pending_slot.is_none(), ErrorCode::LimitOrderPending);
+        
+        // Reserve the slot up front; the callback releases it if the placement fails
+        let slot = limit_order_book
+            .reserve_slot(ctx.accounts.user.key())
+            .ok_or(ErrorCode::LimitOrderBookFull)?;
+        ctx.accounts
+            .market
+            .record_window_trader(&mut ctx.accounts.user_position);
+        
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        
+        let [is_buy_ciphertext, is_yes_ciphertext, size_ciphertext, limit_price_ciphertext] =
+            order_ciphertexts;
+        let args = vec![
+            Argument::ArcisPubkey(order_pub_key),
+            Argument::PlaintextU128(order_nonce),
+            Argument::EncryptedBool(is_buy_ciphertext),
+            Argument::EncryptedBool(is_yes_ciphertext),
+            Argument::EncryptedU64(size_ciphertext),
+            Argument::EncryptedU64(limit_price_ciphertext),
+            Argument::PlaintextU128(ctx.accounts.user_position.nonce),
+            Argument::Account(
+                ctx.accounts.user_position.key(),
+                USER_POSITION_STATE_OFFSET,
+                USER_POSITION_STATE_LEN,
+            ),
+            Argument::PlaintextU128(ctx.accounts.limit_order_book.orders_nonce),
+            Argument::Account(
+                ctx.accounts.limit_order_book.key(),
+                LIMIT_ORDER_BOOK_OFFSET,
+                LIMIT_ORDER_BOOK_LEN,
+            ),
+            Argument::PlaintextU8(slot),
+        ];
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![PlaceLimitOrderCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: ctx.accounts.limit_order_book.key(),
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: ctx.accounts.user_position.key(),
+                    is_writable: true,
+                },
+            ])],
+            1,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "place_limit_order")]
+    pub fn place_limit_order_callback(
+        ctx: Context<PlaceLimitOrderCallback>,
+        output: ComputationOutputs<PlaceLimitOrderOutput>,
+    ) -> Result<()> {
+        let limit_order_book = &mut ctx.accounts.limit_order_book;
+        let o = match output {
+            ComputationOutputs::Success(PlaceLimitOrderOutput { field_0 }) => field_0,
+            _ => {
+                limit_order_book.release_pending_slot(false);
+                return record_position_failure(
+                    &mut ctx.accounts.user_position,
+                    ComputationKind::LimitOrderPlacement,
+                );
+            }
+        };
+        limit_order_book.release_pending_slot(true);
+        limit_order_book.orders_state = o.ciphertexts;
+        limit_order_book.orders_nonce = o.nonce;
+        Ok(())
+    }
+
+    /// Permissionless crank: executes crossing limit orders once the private window has expired,
+    /// before `switch_to_public` reveals prices
+    pub fn match_limit_orders(
+        ctx: Context<MatchLimitOrders>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        use arcium_client::idl::arcium::types::CallbackAccount;
+        
+        let market = &ctx.accounts.market;
+        let limit_order_book = &ctx.accounts.limit_order_book;
+        let clock = Clock::get()?;
+        
+        require!(
+            market.window_state == MarketWindow::Private,
+            ErrorCode::WrongWindowState
+        );
+        require!(!market.pending_transition, ErrorCode::TransitionPending);
+        require!(
+            market.window_expired(clock.unix_timestamp),
+            ErrorCode::WindowNotExpired
+        );
+        require!(!limit_order_book.matched, ErrorCode::LimitOrdersMatched);
+        
+        // Nothing to match: close the book without a computation
+        if limit_order_book.order_count == 0 {
+            ctx.accounts.market.open_limit_book = None;
+            ctx.accounts.limit_order_book.matched = true;
+            
+            emit!(LimitOrdersMatchedEvent {
+                market: ctx.accounts.market.key(),
+                limit_order_book: ctx.accounts.limit_order_book.key(),
+                order_count: 0,
+            });
+            
+            return Ok(());
+        }
+        require!(limit_order_book.orders_nonce != 0, ErrorCode::LimitOrderBookNotReady);
+        require!(limit_order_book.pending_slot.is_none(), ErrorCode::LimitOrderPending);
+        
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        
+        let args = vec![
+            Argument::PlaintextU128(market.nonce),
+            Argument::Account(
+                market.key(),
+                MARKET_STATE_OFFSET,
+                MARKET_STATE_LEN,
+            ),
+            Argument::PlaintextU128(limit_order_book.orders_nonce),
+            Argument::Account(
+                limit_order_book.key(),
+                LIMIT_ORDER_BOOK_OFFSET,
+                LIMIT_ORDER_BOOK_LEN,
+            ),
+            Argument::PlaintextU64(market.state_version),
+        ];
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![MatchLimitOrdersCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: ctx.accounts.market.key(),
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: ctx.accounts.limit_order_book.key(),
+                    is_writable: true,
+                },
+            ])],
+            1,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "match_limit_orders")]
+    pub fn match_limit_orders_callback(
+        ctx: Context<MatchLimitOrdersCallback>,
+        output: ComputationOutputs<MatchLimitOrdersOutput>,
+    ) -> Result<()> {
+        let o = match output {
+            ComputationOutputs::Success(MatchLimitOrdersOutput { field_0 }) => field_0,
+            _ => {
+                return record_market_failure(
+                    &mut ctx.accounts.market,
+                    ComputationKind::MatchLimitOrders,
+                );
+            }
+        };
+        
+        if !advance_state_version(&mut ctx.accounts.market, o.field_2) {
+            // Matched against an older market state; the book can be matched again
+            return record_market_failure(
+                &mut ctx.accounts.market,
+                ComputationKind::MatchLimitOrders,
+            );
+        }
+        
+        let market = &mut ctx.accounts.market;
+        market.market_state = o.field_0.ciphertexts;
+        market.nonce = o.field_0.nonce;
+        market.open_limit_book = None;
+        
+        let limit_order_book = &mut ctx.accounts.limit_order_book;
+        limit_order_book.fills_state = o.field_1.ciphertexts;
+        limit_order_book.fills_nonce = o.field_1.nonce;
+        limit_order_book.matched = true;
+        
+        emit!(LimitOrdersMatchedEvent {
+            market: market.key(),
+            limit_order_book: limit_order_book.key(),
+            order_count: limit_order_book.order_count,
+        });
+        
+        Ok(())
+    }
+
+    /// Permissionless crank: writes one matched slot's fill to its owner's position
+    pub fn apply_limit_fill(
+        ctx: Context<ApplyLimitFill>,
+        computation_offset: u64,
+        slot: u8,
+    ) -> Result<()> {
+        use arcium_client::idl::arcium::types::CallbackAccount;
+        
+        let limit_order_book = &ctx.accounts.limit_order_book;
+        require!(limit_order_book.matched, ErrorCode::LimitOrdersNotMatched);
+        require!(slot < limit_order_book.order_count, ErrorCode::InvalidLimitOrderSlot);
+        require!(
+            !limit_order_book.applied[slot as usize],
+            ErrorCode::OrderAlreadyFilled
+        );
+        require_keys_eq!(
+            limit_order_book.owners[slot as usize],
+            ctx.accounts.user_position.user,
+            ErrorCode::Unauthorized
+        );
+        
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        
+        let args = vec![
+            Argument::PlaintextU128(ctx.accounts.user_position.nonce),
+            Argument::Account(
+                ctx.accounts.user_position.key(),
+                USER_POSITION_STATE_OFFSET,
+                USER_POSITION_STATE_LEN,
+            ),
+            Argument::PlaintextU128(ctx.accounts.limit_order_book.orders_nonce),
+            Argument::Account(
+                ctx.accounts.limit_order_book.key(),
+                LIMIT_ORDER_BOOK_OFFSET,
+                LIMIT_ORDER_BOOK_LEN,
+            ),
+            Argument::PlaintextU128(ctx.accounts.limit_order_book.fills_nonce),
+            Argument::Account(
+                ctx.accounts.limit_order_book.key(),
+                LIMIT_FILLS_OFFSET,
+                LIMIT_FILLS_LEN,
+            ),
+            Argument::PlaintextU8(slot),
+            Argument::PlaintextU64(ctx.accounts.user_position.state_version),
+        ];
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ApplyLimitFillCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: ctx.accounts.user_position.key(),
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: ctx.accounts.limit_order_book.key(),
+                    is_writable: true,
+                },
+            ])],
+            1,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "apply_limit_fill")]
+    pub fn apply_limit_fill_callback(
+        ctx: Context<ApplyLimitFillCallback>,
+        output: ComputationOutputs<ApplyLimitFillOutput>,
+    ) -> Result<()> {
+        let o = match output {
+            ComputationOutputs::Success(ApplyLimitFillOutput { field_0 }) => field_0,
+            _ => {
+                return record_position_failure(
+                    &mut ctx.accounts.user_position,
+                    ComputationKind::LimitFill,
+                );
+            }
+        };
+        let slot = o.field_1 as usize;
+        require!(
+            !ctx.accounts.limit_order_book.applied[slot],
+            ErrorCode::OrderAlreadyFilled
+        );
+        if !advance_position_version(&mut ctx.accounts.user_position, o.field_2) {
+            // The slot stays unapplied so the fill can be cranked again
+            return record_position_failure(
+                &mut ctx.accounts.user_position,
+                ComputationKind::LimitFill,
+            );
+        }
+        
+        let clock = Clock::get()?;
+        ctx.accounts.user_position.position_state = o.field_0.ciphertexts;
+        ctx.accounts.user_position.nonce = o.field_0.nonce;
+        ctx.accounts.user_position.record_trade(clock.unix_timestamp);
+        ctx.accounts.limit_order_book.applied[slot] = true;
+        Ok(())
+    }
+
+    /// Starts a new position aggregation round; positions are then folded in with `aggregate_position`
+    pub fn start_position_aggregate(
+        ctx: Context<StartPositionAggregate>,
+        computation_offset: u64,
+        mxe_nonce: u128,
+    ) -> Result<()> {
+        use arcium_client::idl::arcium::types::CallbackAccount;
+        
+        // Sponsor authorization is enforced by the StartPositionAggregate account constraints
+        let market = &mut ctx.accounts.market;
+        require!(market.has_binary_pools(), ErrorCode::WrongMarketType);
+        market.aggregate_round += 1;
+        
+        let position_aggregate = &mut ctx.accounts.position_aggregate;
+        position_aggregate.bump = ctx.bumps.position_aggregate;
+        position_aggregate.market = market.key();
+        position_aggregate.round = market.aggregate_round;
+        position_aggregate.positions_included = 0;
+        position_aggregate.histogram_state = [[0; 32]; POSITION_HISTOGRAM_ENCRYPTED_FIELDS];
+        position_aggregate.nonce = 0;
+        
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        
+        let args = vec![
+            Argument::PlaintextU128(mxe_nonce),
+        ];
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![InitializePositionHistogramCallback::callback_ix(&[CallbackAccount {
+                pubkey: ctx.accounts.position_aggregate.key(),
+                is_writable: true,
+            }])],
+            1,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "initialize_position_histogram")]
+    pub fn initialize_position_histogram_callback(
+        ctx: Context<InitializePositionHistogramCallback>,
+        output: ComputationOutputs<InitializePositionHistogramOutput>,
+    ) -> Result<()> {
+        let o = match output {
+            ComputationOutputs::Success(InitializePositionHistogramOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+        ctx.accounts.position_aggregate.histogram_state = o.ciphertexts;
+        ctx.accounts.position_aggregate.nonce = o.nonce;
+        Ok(())
+    }
+
+    /// Permissionless crank: folds one position into the current aggregation round
+    pub fn aggregate_position(
+        ctx: Context<AggregatePosition>,
+        computation_offset: u64,
+    ) -> Result<()> {
+        use arcium_client::idl::arcium::types::CallbackAccount;
+        
+        let position_aggregate = &ctx.accounts.position_aggregate;
+        let user_position = &mut ctx.accounts.user_position;
+        require!(position_aggregate.nonce != 0, ErrorCode::AggregateNotReady);
+        require!(
+            user_position.aggregate_round != position_aggregate.round,
+            ErrorCode::PositionAlreadyAggregated
+        );
+        user_position.aggregate_round = position_aggregate.round;
+        
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        
+        let args = vec![
+            Argument::PlaintextU128(ctx.accounts.user_position.nonce),
+            Argument::Account(
+                ctx.accounts.user_position.key(),
+                USER_POSITION_STATE_OFFSET,
+                USER_POSITION_STATE_LEN,
+            ),
+            Argument::PlaintextU128(ctx.accounts.position_aggregate.nonce),
+            Argument::Account(
+                ctx.accounts.position_aggregate.key(),
+                POSITION_AGGREGATE_STATE_OFFSET,
+                POSITION_AGGREGATE_STATE_LEN,
+            ),
+        ];
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![AddPositionToHistogramCallback::callback_ix(&[
+                CallbackAccount {
+                    pubkey: ctx.accounts.position_aggregate.key(),
+                    is_writable: true,
+                },
+                CallbackAccount {
+                    pubkey: ctx.accounts.user_position.key(),
+                    is_writable: true,
+                },
+            ])],
+            1,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "add_position_to_histogram")]
+    pub fn add_position_to_histogram_callback(
+        ctx: Context<AddPositionToHistogramCallback>,
+        output: ComputationOutputs<AddPositionToHistogramOutput>,
+    ) -> Result<()> {
+        let o = match output {
+            ComputationOutputs::Success(AddPositionToHistogramOutput { field_0 }) => field_0,
+            _ => {
+                // Lets the position be added to this round again
+                ctx.accounts.user_position.aggregate_round =
+                    ctx.accounts.position_aggregate.round - 1;
+                return record_position_failure(
+                    &mut ctx.accounts.user_position,
+                    ComputationKind::PositionAggregate,
+                );
+            }
+        };
+        let position_aggregate = &mut ctx.accounts.position_aggregate;
+        position_aggregate.histogram_state = o.ciphertexts;
+        position_aggregate.nonce = o.nonce;
+        position_aggregate.positions_included += 1;
+        Ok(())
+    }
+
+    /// k-anonymous sponsor view: only the histogram, and only once enough staked positions are
+    /// in it. The staked count is encrypted, so the circuit blanks the histogram below the threshold.
+    pub fn get_position_histogram_view(
+        ctx: Context<GetPositionHistogramView>,
+        computation_offset: u64,
+        sponsor_pub_key: [u8; 32],
+    ) -> Result<()> {
+        // Sponsor authorization is enforced by the GetPositionHistogramView account constraints
+        let market = &ctx.accounts.market;
+        let position_aggregate = &ctx.accounts.position_aggregate;
+        require!(
+            position_aggregate.positions_included >= market.min_view_traders.max(1),
+            ErrorCode::NotEnoughTraders
+        );
+        charge_sponsor_view(
+            &mut ctx.accounts.market,
+            &ctx.accounts.sponsor,
+            ctx.accounts.sponsor_token_account.as_deref(),
+            ctx.accounts.market_vault.as_deref(),
+            ctx.accounts.usdc_mint.as_deref(),
+            ctx.accounts.token_program.as_ref(),
+        )?;
+        log_sponsor_view(
+            &mut ctx.accounts.sponsor_access_log,
+            ctx.bumps.sponsor_access_log,
+            ctx.accounts.market.key(),
+            ctx.accounts.sponsor.key(),
+            SponsorViewKind::PositionHistogram,
+            ctx.accounts.position_aggregate.key(),
+        )?;
+        let market = &ctx.accounts.market;
+        let position_aggregate = &ctx.accounts.position_aggregate;
+        
+        ctx.accounts.sign_pda_account.bump = ctx.bumps.sign_pda_account;
+        
+        let args = vec![
+            Argument::PlaintextU128(position_aggregate.nonce),
+            Argument::Account(
+                position_aggregate.key(),
+                POSITION_AGGREGATE_STATE_OFFSET,
+                POSITION_AGGREGATE_STATE_LEN,
+            ),
+            Argument::PlaintextU64(market.min_view_traders as u64),
+            Argument::ArcisPubkey(sponsor_pub_key),
+        ];
+
+        queue_computation(
+            ctx.accounts,
+            computation_offset,
+            args,
+            None,
+            vec![ViewPositionHistogramCallback::callback_ix(&[])],
+            1,
+        )?;
+
+        Ok(())
+    }
+
+    #[arcium_callback(encrypted_ix = "view_position_histogram")]
+    pub fn view_position_histogram_callback(
+        ctx: Context<ViewPositionHistogramCallback>,
+        output: ComputationOutputs<ViewPositionHistogramOutput>,
+    ) -> Result<()> {
+        let o = match output {
+            ComputationOutputs::Success(ViewPositionHistogramOutput { field_0 }) => field_0,
+            _ => return Err(ErrorCode::AbortedComputation.into()),
+        };
+
+        let position_aggregate = &ctx.accounts.position_aggregate;
+        emit!(PositionHistogramViewEvent {
+            market: position_aggregate.market,
+            position_aggregate: position_aggregate.key(),
+            positions_included: position_aggregate.positions_included,
+            encrypted_histogram: o.ciphertexts,
+            nonce: o.nonce,
+        });
+        
+        Ok(())
+    }
+
+    // pub fn claim_payout(
+    //     _ctx: Context<ClaimPayout>,
+    // ) -> Result<()> {
+    //     Ok(())
+    // }
+}
+
+// ========== Market Init Helpers ==========
+
+/// Settings shared by every market type at creation.
+struct MarketParams {
+    question: String,
+    resolution_date: i64,
+    liquidity_cap: u64,
+    initial_liquidity_usdc: u64,
+    opp_window_duration: u64,
+    pub_window_duration: u64,
+    max_views_per_window: u32,
+    view_fee_usdc: u64,
+    /// Positions required before the sponsor may view positions; at least one
+    min_view_traders: u32,
+    window_schedule: Vec<WindowScheduleEntry>,
+}
+
+/// Market creation requires a whitelisted, bonded sponsor and, for multi-signature
+/// sponsors, an approved `CreateMarket` action for this question.
+fn authorize_market_creation(
+    sponsor: &Signer,
+    sponsor_account: &Account<Sponsor>,
+    sponsor_bond: &SponsorBond,
+    config: &ProgramConfig,
+    pending_action: Option<&mut Account<PendingAction>>,
+    question: &str,
+) -> Result<()> {
+    require!(sponsor_account.is_whitelisted, ErrorCode::SponsorNotWhitelisted);
+    require_sponsor_bond(sponsor_bond, config)?;
+
+    let approved = consume_sponsor_approval(
+        sponsor_account,
+        sponsor.key(),
+        pending_action,
+        SponsorAction::CreateMarket {
+            question_hash: hash(question.as_bytes()).to_bytes(),
+        },
+    )?;
+    if !approved {
+        require_keys_eq!(
+            sponsor.key(),
+            sponsor_account.authority,
+            ErrorCode::Unauthorized
+        );
+    }
+    Ok(())
+}
+
+/// Writes every field of a new market as a two-outcome market of `market_type` opening in
+/// the private window, and counts it towards the sponsor's stats. Callers set the
+/// type-specific fields (outcome count, scalar range) afterwards.
+fn init_market_common(
+    market: &mut Account<Market>,
+    bump: u8,
+    sponsor_account: &mut Account<Sponsor>,
+    authority: Pubkey,
+    market_type: MarketType,
+    params: MarketParams,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    validate_window_schedule(&params.window_schedule)?;
+    require!(params.min_view_traders > 0, ErrorCode::InvalidMinViewTraders);
+
+    market.bump = bump;
+    market.sponsor = sponsor_account.key();
+    market.authority = authority;
+    market.question = params.question;
+    market.resolution_date = params.resolution_date;
+    market.window_state = MarketWindow::Private;
+    market.liquidity_cap = params.liquidity_cap;
+    market.initial_liquidity_usdc = params.initial_liquidity_usdc;
+    market.nonce = 0;
+    market.opp_window_duration = params.opp_window_duration;
+    market.pub_window_duration = params.pub_window_duration;
+    market.last_switch_ts = clock.unix_timestamp;
+    market.resolved = false;
+    market.outcome = None;
+    market.invalid = false;
+    market.market_type = market_type;
+    market.num_outcomes = 2;
+    market.group = None;
+    market.scalar_lower = 0;
+    market.scalar_upper = 0;
+    market.scalar_value = None;
+    market.long_payout = 0;
+    market.trading_frozen = false;
+    market.pending_authority = None;
+    market.operator = None;
+    market.created_ts = clock.unix_timestamp;
+    market.attention_hint = None;
+    market.last_hint_ts = 0;
+    market.batch_epoch = 0;
+    market.open_limit_book = None;
+    market.window_traders = 0;
+    market.min_view_traders = params.min_view_traders;
+    market.position_count = 0;
+    market.aggregate_round = 0;
+    market.public_sponsor_views = 0;
+    market.max_views_per_window = params.max_views_per_window;
+    market.view_fee_usdc = params.view_fee_usdc;
+    market.window_sponsor_views = 0;
+    market.view_fees_collected = 0;
+    market.window_schedule = params.window_schedule;
+    market.schedule_index = 0;
+    market.pending_transition = false;
+    market.state_version = 0;
+    market.failed_computations = 0;
+    market.last_failed_computation = None;
+    market.last_failure_ts = 0;
+    market.private_window_opened = false;
+    // Filled in by the initialize computation's callback
+    market.market_state = [[0; 32]; MARKET_STATE_ENCRYPTED_FIELDS];
+
+    sponsor_account.total_markets_created += 1;
+    sponsor_account.total_liquidity_provided = sponsor_account
+        .total_liquidity_provided
+        .checked_add(params.initial_liquidity_usdc)
+        .ok_or(ErrorCode::Overflow)?;
+
+    Ok(())
+}
+
+// ========== Categorical Market Helpers ==========
+
+/// Categorical trades and the position updates that go with them run only in the
+/// private window of an open, unfrozen categorical market.
+fn require_categorical_trading(market: &Market) -> Result<()> {
+    require!(
+        market.market_type == MarketType::Categorical,
+        ErrorCode::WrongMarketType
+    );
+    require!(
+        market.window_state == MarketWindow::Private,
+        ErrorCode::WrongWindowState
+    );
+    require!(!market.resolved, ErrorCode::MarketResolved);
+    require!(!market.trading_frozen, ErrorCode::TradingFrozen);
+    require!(!market.pending_transition, ErrorCode::TransitionPending);
+    Ok(())
+}
+
+// ========== USDC Vault Helpers ==========
+
+/// Moves USDC between token accounts with `transfer_checked`. Transfers out of a PDA-owned
+/// vault pass the PDA's seeds; transfers signed by a wallet pass none.
+fn transfer_usdc<'info>(
+    from: &Account<'info, TokenAccount>,
+    to: &Account<'info, TokenAccount>,
+    authority: AccountInfo<'info>,
+    mint: &Account<'info, Mint>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    token::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: from.to_account_info(),
+                mint: mint.to_account_info(),
+                to: to.to_account_info(),
+                authority,
+            },
+            signer_seeds,
+        ),
+        amount,
+        mint.decimals,
+    )
+}
+
+/// Pays USDC out of a market's vault, signed by the market PDA that owns it.
+fn pay_from_market_vault<'info>(
+    market: &Account<'info, Market>,
+    market_vault: &Account<'info, TokenAccount>,
+    to: &Account<'info, TokenAccount>,
+    mint: &Account<'info, Mint>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    let bump = [market.bump];
+    let seeds: &[&[u8]] = &[
+        b"market",
+        market.sponsor.as_ref(),
+        market.question.as_bytes(),
+        &bump,
+    ];
+    transfer_usdc(
+        market_vault,
+        to,
+        market.to_account_info(),
+        mint,
+        token_program,
+        amount,
+        &[seeds],
+    )
+}
+
+// ========== Sponsor Approval Helpers ==========
+
+/// For multi-signature sponsors, checks that `signer` is in the signer set and that
+/// `pending_action` carries `expected` with enough approvals, then marks it executed.
+/// Returns `false` for single-key sponsors, leaving the caller to check the signer itself.
+fn consume_sponsor_approval(
+    sponsor: &Account<Sponsor>,
+    signer: Pubkey,
+    pending_action: Option<&mut Account<PendingAction>>,
+    expected: SponsorAction,
+) -> Result<bool> {
+    if !sponsor.is_multisig() {
+        return Ok(false);
+    }
+
+    require!(sponsor.is_signer(&signer), ErrorCode::Unauthorized);
+
+    let pending_action = pending_action.ok_or(ErrorCode::ApprovalRequired)?;
+    require_keys_eq!(pending_action.sponsor, sponsor.key(), ErrorCode::ApprovalRequired);
+    require!(pending_action.action == expected, ErrorCode::ApprovalRequired);
+    require!(!pending_action.executed, ErrorCode::ActionAlreadyExecuted);
+
+    // Only approvals from keys still in the signer set count
+    let approvals = pending_action
+        .approvals
+        .iter()
+        .filter(|key| sponsor.is_signer(key))
+        .count();
+    require!(
+        approvals >= sponsor.threshold as usize,
+        ErrorCode::InsufficientApprovals
+    );
+
+    pending_action.executed = true;
+    Ok(true)
+}
+
+fn hash_signers(signers: &[Pubkey]) -> [u8; 32] {
+    let bytes: Vec<u8> = signers.iter().flat_map(|key| key.to_bytes()).collect();
+    hash(&bytes).to_bytes()
+}
+
+// ========== Sponsor Bond Helpers ==========
+
+/// Market creation requires a bond at or above the configured minimum that is not being unstaked.
+fn require_sponsor_bond(sponsor_bond: &SponsorBond, config: &ProgramConfig) -> Result<()> {
+    require!(
+        sponsor_bond.unstake_requested_ts.is_none(),
+        ErrorCode::UnstakePending
+    );
+    require!(
+        sponsor_bond.amount >= config.min_sponsor_bond,
+        ErrorCode::InsufficientSponsorBond
+    );
+    Ok(())
+}
+
+// ========== Order Batch Helpers ==========
+
+/// The current epoch's batch, if one was opened, must clear before the window switches so
+/// no submitted order is left unpriced.
+fn require_batch_cleared(order_batch: &AccountInfo) -> Result<()> {
+    if order_batch.data_is_empty() {
+        return Ok(());
+    }
+    // Seeds pin the address to this program's PDA, so only the data needs checking
+    let data = order_batch.try_borrow_data()?;
+    let order_batch = OrderBatch::try_deserialize(&mut &data[..])?;
+    require!(order_batch.cleared, ErrorCode::BatchNotCleared);
+    Ok(())
+}
+
+// ========== Computation Failure Helpers ==========
+
+/// Records a failed market computation instead of reverting, so the failure is visible
+/// and the caller can queue the computation again.
+fn record_market_failure(market: &mut Account<Market>, computation: ComputationKind) -> Result<()> {
+    let timestamp = Clock::get()?.unix_timestamp;
+    market.record_failure(computation, timestamp);
+    
+    emit!(ComputationFailedEvent {
+        account: market.key(),
+        computation,
+        timestamp,
+    });
+    
+    Ok(())
+}
+
+/// Records a failed position computation so the trader learns their update did not apply.
+fn record_categorical_position_failure(
+    categorical_position: &mut Account<CategoricalPosition>,
+    computation: ComputationKind,
+) -> Result<()> {
+    let timestamp = Clock::get()?.unix_timestamp;
+    categorical_position.record_failure(timestamp);
+    
+    emit!(ComputationFailedEvent {
+        account: categorical_position.key(),
+        computation,
+        timestamp,
+    });
+    
+    Ok(())
+}
+
+/// A categorical trade updates the pools and the position together, so its failure is
+/// recorded on both.
+fn record_categorical_trade_failure(
+    market: &mut Account<Market>,
+    categorical_position: &mut Account<CategoricalPosition>,
+) -> Result<()> {
+    record_categorical_position_failure(categorical_position, ComputationKind::PrivateTrade)?;
+    record_market_failure(market, ComputationKind::PrivateTrade)
+}
+
+fn record_position_failure(
+    user_position: &mut Account<UserPosition>,
+    computation: ComputationKind,
+) -> Result<()> {
+    let timestamp = Clock::get()?.unix_timestamp;
+    user_position.record_failure(timestamp);
+    
+    emit!(ComputationFailedEvent {
+        account: user_position.key(),
+        computation,
+        timestamp,
+    });
+    
+    Ok(())
+}
+
+// ========== Market State Version Helpers ==========
+
+/// Bumps the version for a callback's write of output computed from the current `market_state`.
+/// Returns `false` for output computed from an older state, which the callback records as a failure.
+fn advance_state_version(market: &mut Market, computed_at_version: u64) -> bool {
+    if computed_at_version != market.state_version {
+        return false;
+    }
+    market.state_version += 1;
+    true
+}
+
+/// Same as `advance_state_version`, for a trader's `position_state`.
+fn advance_position_version(user_position: &mut UserPosition, computed_at_version: u64) -> bool {
+    if computed_at_version != user_position.state_version {
+        return false;
+    }
+    user_position.state_version += 1;
+    true
+}
+
+/// Same as `advance_state_version`, for a categorical trader's `position_state`.
+fn advance_categorical_position_version(
+    categorical_position: &mut CategoricalPosition,
+    computed_at_version: u64,
+) -> bool {
+    if computed_at_version != categorical_position.state_version {
+        return false;
+    }
+    categorical_position.state_version += 1;
+    true
+}
+
+// ========== Window Schedule Helpers ==========
+
+/// Markets open in the private window, so a schedule must start there and alternate.
+/// Only the last entry may use duration 0 (stay in that window until resolution).
+fn validate_window_schedule(schedule: &[WindowScheduleEntry]) -> Result<()> {
+    require!(
+        schedule.len() <= MAX_WINDOW_SCHEDULE_ENTRIES,
+        ErrorCode::InvalidWindowSchedule
+    );
+    let mut expected = MarketWindow::Private;
+    for (i, entry) in schedule.iter().enumerate() {
+        require!(entry.window == expected, ErrorCode::InvalidWindowSchedule);
+        require!(
+            entry.duration > 0 || i == schedule.len() - 1,
+            ErrorCode::InvalidWindowSchedule
+        );
+        expected = match entry.window {
+            MarketWindow::Private => MarketWindow::Public,
+            MarketWindow::Public => MarketWindow::Private,
+        };
+    }
+    Ok(())
+}
+
+// ========== Sponsor Access Log Helpers ==========
+
+/// Records a sponsor view request, initializing the market's log on first use.
+fn log_sponsor_view(
+    log: &mut SponsorAccessLog,
+    bump: u8,
+    market: Pubkey,
+    viewer: Pubkey,
+    kind: SponsorViewKind,
+    target: Pubkey,
+) -> Result<()> {
+    if log.market == Pubkey::default() {
+        log.bump = bump;
+        log.market = market;
+    }
+    log.record(SponsorAccessEntry {
+        viewer,
+        kind,
+        target,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Total views in a market's access log, zero if the sponsor never requested one.
+fn sponsor_view_count(log: &AccountInfo) -> Result<u32> {
+    if log.data_is_empty() {
+        return Ok(0);
+    }
+    let data = log.try_borrow_data()?;
+    let log = SponsorAccessLog::try_deserialize(&mut &data[..])?;
+    Ok(log.total_views())
+}
+
+/// Enforces the market's per-window view limit and collects the view fee in USDC into the
+/// market vault. The fee accounts are only needed when the market charges for views.
+fn charge_sponsor_view<'info>(
+    market: &mut Account<'info, Market>,
+    sponsor: &Signer<'info>,
+    sponsor_token_account: Option<&Account<'info, TokenAccount>>,
+    market_vault: Option<&Account<'info, TokenAccount>>,
+    usdc_mint: Option<&Account<'info, Mint>>,
+    token_program: Option<&Program<'info, Token>>,
+) -> Result<()> {
+    require!(market.sponsor_view_available(), ErrorCode::SponsorViewLimitReached);
+    if market.view_fee_usdc > 0 {
+        let (Some(sponsor_token_account), Some(market_vault), Some(usdc_mint), Some(token_program)) =
+            (sponsor_token_account, market_vault, usdc_mint, token_program)
+        else {
+            return err!(ErrorCode::ViewFeeAccountsMissing);
+        };
+        transfer_usdc(
+            sponsor_token_account,
+            market_vault,
+            sponsor.to_account_info(),
+            usdc_mint,
+            token_program,
+            market.view_fee_usdc,
+            &[],
+        )?;
+        market.view_fees_collected = market
+            .view_fees_collected
+            .checked_add(market.view_fee_usdc)
+            .ok_or(ErrorCode::Overflow)?;
+    }
+    market.window_sponsor_views += 1;
+    Ok(())
+}
+
+// ========== Resolution Helpers ==========
+
+/// Takes the proposer's bond and opens the dispute window for `outcome`/`value`.
+fn open_resolution_proposal(
+    accounts: &mut ProposeResolution,
+    bump: u8,
+    outcome: u8,
+    value: u64,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let resolution_bond = accounts.config.resolution_bond;
+    
+    // Bond is held as lamports in the proposal account until the resolution settles
+    anchor_lang::system_program::transfer(
+        CpiContext::new(
+            accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: accounts.authority.to_account_info(),
+                to: accounts.proposal.to_account_info(),
+            },
+        ),
+        resolution_bond,
+    )?;
+    
+    let dispute_deadline = clock.unix_timestamp + accounts.config.dispute_period;
+    let proposal = &mut accounts.proposal;
+    proposal.bump = bump;
+    proposal.market = accounts.market.key();
+    proposal.proposer = accounts.authority.key();
+    proposal.outcome = outcome;
+    proposal.value = value;
+    proposal.bond = resolution_bond;
+    proposal.proposed_ts = clock.unix_timestamp;
+    proposal.dispute_deadline = dispute_deadline;
+    proposal.disputer = None;
+    
+    emit!(ResolutionProposedEvent {
+        market: accounts.market.key(),
+        outcome,
+        value,
+        dispute_deadline,
+    });
+    
+    Ok(())
+}
+
+/// Applies a final outcome (or value for scalar markets) to a market, keeping
+/// its `MarketGroup` counters in sync when the market belongs to one.
+fn settle_market(
+    market: &mut Account<Market>,
+    sponsor: &mut Account<Sponsor>,
+    market_group: Option<&mut Account<MarketGroup>>,
+    outcome: u8,
+    value: u64,
+) -> Result<()> {
+    require!(!market.resolved, ErrorCode::MarketResolved);
+
+    if market.market_type == MarketType::Scalar {
+        let long_payout = market.scalar_long_payout(value);
+
+        market.resolved = true;
+        market.scalar_value = Some(value);
+        market.long_payout = long_payout;
+
+        emit!(ScalarMarketResolvedEvent {
+            market: market.key(),
+            value,
+            long_payout,
+            short_payout: 1000 - long_payout,
+        });
+        return record_sponsor_resolution(sponsor, market);
+    }
+
+    require!(outcome < market.num_outcomes, ErrorCode::InvalidOutcome);
+
+    if let Some(group_key) = market.group {
+        let group = market_group.ok_or(ErrorCode::MarketNotInGroup)?;
+        require_keys_eq!(group.key(), group_key, ErrorCode::MarketNotInGroup);
+
+        // Once N markets resolved YES the remaining ones can only resolve NO
+        let completed = group.record_outcome(outcome).ok_or(ErrorCode::GroupCompleted)?;
+        if completed {
+            emit!(MarketGroupCompletedEvent {
+                group: group.key(),
+                resolved_yes: group.resolved_yes,
+            });
+        }
+    }
+
+    market.resolved = true;
+    market.outcome = Some(outcome);
+
+    emit!(MarketResolvedEvent {
+        market: market.key(),
+        outcome,
+    });
+
+    record_sponsor_resolution(sponsor, market)
+}
+
+/// Adds a just-resolved market to its sponsor's reputation ledger.
+fn record_sponsor_resolution(sponsor: &mut Account<Sponsor>, market: &Market) -> Result<()> {
+    let clock = Clock::get()?;
+
+    sponsor
+        .record_resolution(market, clock.unix_timestamp)
+        .ok_or(ErrorCode::Overflow)?;
+
+    emit_sponsor_stats(sponsor);
+    Ok(())
+}
+
+fn emit_sponsor_stats(sponsor: &Account<Sponsor>) {
+    emit!(SponsorStatsUpdated {
+        sponsor: sponsor.key(),
+        total_markets_created: sponsor.total_markets_created,
+        markets_resolved_yes: sponsor.markets_resolved_yes,
+        markets_resolved_no: sponsor.markets_resolved_no,
+        markets_resolved_invalid: sponsor.markets_resolved_invalid,
+        resolutions_disputed: sponsor.resolutions_disputed,
+        resolutions_overturned: sponsor.resolutions_overturned,
+        average_resolution_secs: sponsor.average_resolution_secs(),
+        total_liquidity_provided: sponsor.total_liquidity_provided,
+    });
+}
+
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct InitSponsor<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = Sponsor::SPACE,
+        seeds = [b"sponsor", authority.key().as_ref()],
+        bump
+    )]
+    pub sponsor: Account<'info, Sponsor>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WhitelistSponsor<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(mut)]
+    pub sponsor: Account<'info, Sponsor>,
+}
+
+#[derive(Accounts)]
+pub struct SetSponsorSigners<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(mut)]
+    pub sponsor_account: Account<'info, Sponsor>,
+    
+    /// Required when the sponsor is already multi-signature
+    #[account(mut)]
+    pub pending_action: Option<Account<'info, PendingAction>>,
+}
+
+#[derive(Accounts)]
+#[instruction(action_id: u64)]
+pub struct CreatePendingAction<'info> {
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    
+    pub sponsor_account: Account<'info, Sponsor>,
+    
+    #[account(
+        init,
+        payer = signer,
+        space = PendingAction::SPACE,
+        seeds = [b"pending_action", sponsor_account.key().as_ref(), &action_id.to_le_bytes()],
+        bump
+    )]
+    pub pending_action: Account<'info, PendingAction>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApprovePendingAction<'info> {
+    pub signer: Signer<'info>,
+    
+    pub sponsor_account: Account<'info, Sponsor>,
+    
+    #[account(
+        mut,
+        constraint = pending_action.sponsor == sponsor_account.key() @ ErrorCode::Unauthorized
+    )]
+    pub pending_action: Account<'info, PendingAction>,
+}
+
+#[derive(Accounts)]
+pub struct InitConfig<'info> {
+    /// Must be the program's upgrade authority, so only the deployer can claim the config
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::PythiaOp>,
+    
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(admin.key())
+            @ ErrorCode::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = ProgramConfig::SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    pub usdc_mint: Account<'info, Mint>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub admin: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProgramConfig>,
+}
+
+#[derive(Accounts)]
+pub struct StakeSponsorBond<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        constraint = sponsor_account.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub sponsor_account: Account<'info, Sponsor>,
+    
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = SponsorBond::SPACE,
+        seeds = [b"sponsor_bond", sponsor_account.key().as_ref()],
+        bump
+    )]
+    pub sponsor_bond: Account<'info, SponsorBond>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(address = config.usdc_mint @ ErrorCode::InvalidMint)]
+    pub usdc_mint: Box<Account<'info, Mint>>,
+    
+    #[account(
+        mut,
+        token::mint = usdc_mint,
+        token::authority = authority
+    )]
+    pub authority_token_account: Box<Account<'info, TokenAccount>>,
+    
+    /// Holds the staked USDC, owned by the sponsor bond PDA
+    #[account(
+        init_if_needed,
+        payer = authority,
+        token::mint = usdc_mint,
+        token::authority = sponsor_bond,
+        seeds = [b"sponsor_bond_vault", sponsor_bond.key().as_ref()],
+        bump
+    )]
+    pub bond_vault: Box<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateSponsorBond<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        constraint = sponsor_account.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub sponsor_account: Account<'info, Sponsor>,
+    
+    #[account(
+        mut,
+        seeds = [b"sponsor_bond", sponsor_account.key().as_ref()],
+        bump = sponsor_bond.bump
+    )]
+    pub sponsor_bond: Account<'info, SponsorBond>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawSponsorBond<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        constraint = sponsor_account.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub sponsor_account: Account<'info, Sponsor>,
+    
+    #[account(
+        mut,
+        seeds = [b"sponsor_bond", sponsor_account.key().as_ref()],
+        bump = sponsor_bond.bump
+    )]
+    pub sponsor_bond: Account<'info, SponsorBond>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(address = config.usdc_mint @ ErrorCode::InvalidMint)]
+    pub usdc_mint: Box<Account<'info, Mint>>,
+    
+    #[account(mut, token::mint = usdc_mint)]
+    pub authority_token_account: Box<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"sponsor_bond_vault", sponsor_bond.key().as_ref()],
+        bump
+    )]
+    pub bond_vault: Box<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SlashSponsorBond<'info> {
+    pub admin: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    pub sponsor_account: Account<'info, Sponsor>,
+    
+    #[account(
+        mut,
+        seeds = [b"sponsor_bond", sponsor_account.key().as_ref()],
+        bump = sponsor_bond.bump
+    )]
+    pub sponsor_bond: Account<'info, SponsorBond>,
+    
+    #[account(address = config.usdc_mint @ ErrorCode::InvalidMint)]
+    pub usdc_mint: Box<Account<'info, Mint>>,
+    
+    #[account(
+        mut,
+        seeds = [b"sponsor_bond_vault", sponsor_bond.key().as_ref()],
+        bump
+    )]
+    pub bond_vault: Box<Account<'info, TokenAccount>>,
+    
+    /// Receives the slashed USDC, chosen by the admin (typically the disputer)
+    #[account(mut, token::mint = usdc_mint)]
+    pub recipient_token_account: Box<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawViewFees<'info> {
+    pub admin: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(mut)]
+    pub market: Box<Account<'info, Market>>,
+    
+    #[account(address = config.usdc_mint @ ErrorCode::InvalidMint)]
+    pub usdc_mint: Box<Account<'info, Mint>>,
+    
+    #[account(mut, seeds = [b"market_vault", market.key().as_ref()], bump)]
+    pub market_vault: Box<Account<'info, TokenAccount>>,
+    
+    /// Admin's USDC account receiving the collected view fees
+    #[account(
+        mut,
+        token::mint = usdc_mint,
+        token::authority = config.admin
+    )]
+    pub recipient_token_account: Box<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(question: String)]
+pub struct InitMarket<'info> {
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+    
+    #[account(mut)]
+    pub sponsor_account: Account<'info, Sponsor>,
+    
+    #[account(
+        seeds = [b"sponsor_bond", sponsor_account.key().as_ref()],
+        bump = sponsor_bond.bump
+    )]
+    pub sponsor_bond: Account<'info, SponsorBond>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(
+        init,
+        payer = sponsor,
+        space = Market::SPACE,
+        seeds = [b"market", sponsor_account.key().as_ref(), question.as_bytes()],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    /// Required when the sponsor is multi-signature
+    #[account(mut)]
+    pub pending_action: Option<Account<'info, PendingAction>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("initialize_market", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct InitMarketEncrypted<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INITIALIZE_MARKET))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("initialize_market")]
+#[derive(Accounts)]
+pub struct InitializeMarketCallback<'info> {
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INITIALIZE_MARKET))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("initialize_user_position", user)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct InitUserPosition<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        init,
+        payer = user,
+        space = 8 + 1 + 32 + 32 + (32 * USER_POSITION_ENCRYPTED_FIELDS) + 16 + (1 + 8) + 1 + 1 + 8 + 8 + 4 + 8 + 8 + 8 + 8 + 4 + (32 * 10), // Added space for close_records Vec (initial capacity for 10 records)
+        seeds = [b"user_position", market.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_position: Account<'info, UserPosition>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = user,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INITIALIZE_USER_POSITION))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("initialize_user_position")]
+#[derive(Accounts)]
+pub struct InitializeUserPositionCallback<'info> {
+    #[account(mut)]
+    pub user_position: Account<'info, UserPosition>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INITIALIZE_USER_POSITION))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("close_position", user)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ClosePositionPrivate<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub market: Box<Account<'info, Market>>,
+    
+    #[account(
+        mut,
+        seeds = [b"user_position", market.key().as_ref(), user.key().as_ref()],
+        bump = user_position.bump,
+        has_one = user,
+        has_one = market
+    )]
+    pub user_position: Box<Account<'info, UserPosition>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = user,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CLOSE_POSITION))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("close_position")]
+#[derive(Accounts)]
+pub struct ClosePositionCallback<'info> {
+    #[account(mut)]
+    pub user_position: Account<'info, UserPosition>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CLOSE_POSITION))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("reveal_market_state", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct SwitchToPublic<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    
+    /// CHECK: may not exist yet if the sponsor never requested a view; read by `sponsor_view_count`
+    #[account(
+        seeds = [b"sponsor_access_log", market.key().as_ref()],
+        bump
+    )]
+    pub sponsor_access_log: UncheckedAccount<'info>,
+    
+    /// CHECK: the current epoch's order batch, which may not exist; read by `require_batch_cleared`
+    #[account(
+        seeds = [b"order_batch", market.key().as_ref(), &market.batch_epoch.to_le_bytes()],
+        bump
+    )]
+    pub order_batch: UncheckedAccount<'info>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_MARKET_STATE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("reveal_market_state")]
+#[derive(Accounts)]
+pub struct RevealMarketStateCallback<'info> {
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_MARKET_STATE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct TradePublic<'info> {
+    #[account(mut)]
+    pub trader: Signer<'info>,
+    
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+}
+
+#[queue_computation_accounts("hide_market_state", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct SwitchToPrivate<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_HIDE_MARKET_STATE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("hide_market_state")]
+#[derive(Accounts)]
+pub struct HideMarketStateCallback<'info> {
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_HIDE_MARKET_STATE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("view_market_state", sponsor)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct GetSponsorView<'info> {
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+    
+    pub sponsor_account: Account<'info, Sponsor>,
+    
+    /// Sponsor authority, market authority or the market's operator
+    #[account(
+        mut,
+        constraint = market.sponsor == sponsor_account.key() @ ErrorCode::Unauthorized,
+        constraint = sponsor_account.authority == sponsor.key()
+            || market.can_operate(&sponsor.key()) @ ErrorCode::Unauthorized
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        init_if_needed,
+        payer = sponsor,
+        space = SponsorAccessLog::SPACE,
+        seeds = [b"sponsor_access_log", market.key().as_ref()],
+        bump
+    )]
+    pub sponsor_access_log: Box<Account<'info, SponsorAccessLog>>,
+    
+    /// Sponsor's USDC account paying the view fee, required when the market charges one
+    #[account(mut, token::authority = sponsor)]
+    pub sponsor_token_account: Option<Box<Account<'info, TokenAccount>>>,
+    
+    #[account(mut, seeds = [b"market_vault", market.key().as_ref()], bump)]
+    pub market_vault: Option<Box<Account<'info, TokenAccount>>>,
+    
+    pub usdc_mint: Option<Box<Account<'info, Mint>>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = sponsor,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_VIEW_MARKET_STATE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("view_market_state")]
+#[derive(Accounts)]
+pub struct ViewMarketStateCallback<'info> {
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_VIEW_MARKET_STATE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("view_market_summary", sponsor)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct GetSponsorMarketSummary<'info> {
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+    
+    pub sponsor_account: Account<'info, Sponsor>,
+    
+    /// Sponsor authority, market authority or the market's operator
+    #[account(
+        mut,
+        constraint = market.sponsor == sponsor_account.key() @ ErrorCode::Unauthorized,
+        constraint = sponsor_account.authority == sponsor.key()
+            || market.can_operate(&sponsor.key()) @ ErrorCode::Unauthorized
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        init_if_needed,
+        payer = sponsor,
+        space = SponsorAccessLog::SPACE,
+        seeds = [b"sponsor_access_log", market.key().as_ref()],
+        bump
+    )]
+    pub sponsor_access_log: Box<Account<'info, SponsorAccessLog>>,
+    
+    /// Sponsor's USDC account paying the view fee, required when the market charges one
+    #[account(mut, token::authority = sponsor)]
+    pub sponsor_token_account: Option<Box<Account<'info, TokenAccount>>>,
+    
+    #[account(mut, seeds = [b"market_vault", market.key().as_ref()], bump)]
+    pub market_vault: Option<Box<Account<'info, TokenAccount>>>,
+    
+    pub usdc_mint: Option<Box<Account<'info, Mint>>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = sponsor,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_VIEW_MARKET_SUMMARY))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("view_market_summary")]
+#[derive(Accounts)]
+pub struct ViewMarketSummaryCallback<'info> {
+    pub market: Account<'info, Market>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_VIEW_MARKET_SUMMARY))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("view_user_position", sponsor)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct GetUserPositionView<'info> {
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+    
+    pub sponsor_account: Account<'info, Sponsor>,
+    
+    /// Sponsor authority, market authority or the market's operator
+    #[account(
+        mut,
+        constraint = market.sponsor == sponsor_account.key() @ ErrorCode::Unauthorized,
+        constraint = sponsor_account.authority == sponsor.key()
+            || market.can_operate(&sponsor.key()) @ ErrorCode::Unauthorized
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        init_if_needed,
+        payer = sponsor,
+        space = SponsorAccessLog::SPACE,
+        seeds = [b"sponsor_access_log", market.key().as_ref()],
+        bump
+    )]
+    pub sponsor_access_log: Box<Account<'info, SponsorAccessLog>>,
+    
+    /// Sponsor's USDC account paying the view fee, required when the market charges one
+    #[account(mut, token::authority = sponsor)]
+    pub sponsor_token_account: Option<Box<Account<'info, TokenAccount>>>,
+    
+    #[account(mut, seeds = [b"market_vault", market.key().as_ref()], bump)]
+    pub market_vault: Option<Box<Account<'info, TokenAccount>>>,
+    
+    pub usdc_mint: Option<Box<Account<'info, Mint>>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+    
+    #[account(
+        has_one = market @ ErrorCode::Unauthorized
+    )]
+    pub user_position: Account<'info, UserPosition>,
+    
+    /// Current aggregation round; its encrypted staked count gates the view
+    #[account(
+        seeds = [
+            b"position_aggregate",
+            market.key().as_ref(),
+            &market.aggregate_round.to_le_bytes()
+        ],
+        bump = position_aggregate.bump,
+        has_one = market @ ErrorCode::Unauthorized
+    )]
+    pub position_aggregate: Box<Account<'info, PositionAggregate>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = sponsor,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_VIEW_USER_POSITION))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Account<'info, Cluster>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("view_user_position")]
+#[derive(Accounts)]
+pub struct ViewUserPositionCallback<'info> {
+    #[account(mut)]
+    pub user_position: Account<'info, UserPosition>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_VIEW_USER_POSITION))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("view_my_position", user)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ViewMyPosition<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        has_one = user @ ErrorCode::Unauthorized
+    )]
+    pub user_position: Account<'info, UserPosition>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = user,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_VIEW_MY_POSITION))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("view_my_position")]
+#[derive(Accounts)]
+pub struct ViewMyPositionCallback<'info> {
+    pub user_position: Account<'info, UserPosition>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_VIEW_MY_POSITION))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(question: String)]
+pub struct InitCategoricalMarket<'info> {
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+    
+    #[account(mut)]
+    pub sponsor_account: Account<'info, Sponsor>,
+    
+    #[account(
+        seeds = [b"sponsor_bond", sponsor_account.key().as_ref()],
+        bump = sponsor_bond.bump
+    )]
+    pub sponsor_bond: Account<'info, SponsorBond>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+    
+    /// Required when the sponsor is multi-signature
+    #[account(mut)]
+    pub pending_action: Option<Account<'info, PendingAction>>,
+    
+    #[account(
+        init,
+        payer = sponsor,
+        space = Market::SPACE,
+        seeds = [b"market", sponsor_account.key().as_ref(), question.as_bytes()],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(
+        init,
+        payer = sponsor,
+        space = 8 + 1 + 32 + (32 * CATEGORICAL_STATE_ENCRYPTED_FIELDS) + 16,
+        seeds = [b"categorical_state", market.key().as_ref()],
+        bump
+    )]
+    pub categorical_state: Account<'info, CategoricalState>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("initialize_categorical_market", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct InitCategoricalMarketEncrypted<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub market: Box<Account<'info, Market>>,
+    
+    #[account(
+        mut,
+        seeds = [b"categorical_state", market.key().as_ref()],
+        bump = categorical_state.bump,
+        has_one = market
+    )]
+    pub categorical_state: Box<Account<'info, CategoricalState>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INITIALIZE_CATEGORICAL_MARKET))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("initialize_categorical_market")]
+#[derive(Accounts)]
+pub struct InitializeCategoricalMarketCallback<'info> {
+    #[account(mut)]
+    pub categorical_state: Account<'info, CategoricalState>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INITIALIZE_CATEGORICAL_MARKET))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("process_categorical_trade", user)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct TradeCategoricalPrivate<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub market: Box<Account<'info, Market>>,
+    
+    #[account(
+        mut,
+        seeds = [b"categorical_state", market.key().as_ref()],
+        bump = categorical_state.bump,
+        has_one = market
+    )]
+    pub categorical_state: Box<Account<'info, CategoricalState>>,
+    
+    #[account(
+        mut,
+        seeds = [b"categorical_position", market.key().as_ref(), user.key().as_ref()],
+        bump = categorical_position.bump,
+        has_one = user,
+        has_one = market
+    )]
+    pub categorical_position: Box<Account<'info, CategoricalPosition>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = user,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PROCESS_CATEGORICAL_TRADE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("process_categorical_trade")]
+#[derive(Accounts)]
+pub struct ProcessCategoricalTradeCallback<'info> {
+    #[account(mut)]
+    pub categorical_state: Account<'info, CategoricalState>,
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    #[account(mut)]
+    pub categorical_position: Account<'info, CategoricalPosition>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PROCESS_CATEGORICAL_TRADE))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("initialize_categorical_position", user)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct InitCategoricalPosition<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub market: Box<Account<'info, Market>>,
+    
+    #[account(
+        init,
+        payer = user,
+        space = 8 + 1 + 32 + 32 + (32 * CATEGORICAL_POSITION_ENCRYPTED_FIELDS) + 16 + (1 + 8)
+            + 1 + 4 + 8 + 8,
+        seeds = [b"categorical_position", market.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub categorical_position: Box<Account<'info, CategoricalPosition>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = user,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INITIALIZE_CATEGORICAL_POSITION))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("initialize_categorical_position")]
+#[derive(Accounts)]
+pub struct InitializeCategoricalPositionCallback<'info> {
+    #[account(mut)]
+    pub categorical_position: Account<'info, CategoricalPosition>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INITIALIZE_CATEGORICAL_POSITION))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMarketAuthority<'info> {
+    /// Market authority, or a signer of a multi-signature sponsor
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = market.sponsor == sponsor_account.key() @ ErrorCode::Unauthorized
+    )]
+    pub market: Account<'info, Market>,
+    
+    pub sponsor_account: Account<'info, Sponsor>,
+    
+    /// Required when the sponsor is multi-signature
+    #[account(mut)]
+    pub pending_action: Option<Account<'info, PendingAction>>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptMarketAuthority<'info> {
+    pub new_authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = market.pending_authority == Some(new_authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub market: Account<'info, Market>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeResolution<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = market.sponsor == sponsor_account.key() @ ErrorCode::Unauthorized
+    )]
+    pub market: Account<'info, Market>,
+    
+    pub sponsor_account: Account<'info, Sponsor>,
+    
+    /// Required when the sponsor is multi-signature
+    #[account(mut)]
+    pub pending_action: Option<Account<'info, PendingAction>>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = ResolutionProposal::SPACE,
+        seeds = [b"resolution", market.key().as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, ResolutionProposal>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DisputeResolution<'info> {
+    #[account(mut)]
+    pub disputer: Signer<'info>,
+    
+    pub market: Account<'info, Market>,
+    
+    #[account(mut, address = market.sponsor @ ErrorCode::Unauthorized)]
+    pub sponsor_account: Account<'info, Sponsor>,
+    
+    /// Only traders holding a position in the market can dispute: a `UserPosition` for
+    /// binary and scalar markets, a `CategoricalPosition` for categorical markets
+    #[account(
+        seeds = [b"user_position", market.key().as_ref(), disputer.key().as_ref()],
+        bump = user_position.bump,
+        constraint = user_position.user == disputer.key() @ ErrorCode::Unauthorized,
+        has_one = market
+    )]
+    pub user_position: Option<Account<'info, UserPosition>>,
+    
+    #[account(
+        seeds = [b"categorical_position", market.key().as_ref(), disputer.key().as_ref()],
+        bump = categorical_position.bump,
+        constraint = categorical_position.user == disputer.key() @ ErrorCode::Unauthorized,
+        has_one = market
+    )]
+    pub categorical_position: Option<Account<'info, CategoricalPosition>>,
+    
+    #[account(
+        mut,
+        seeds = [b"resolution", market.key().as_ref()],
+        bump = proposal.bump,
+        has_one = market
+    )]
+    pub proposal: Account<'info, ResolutionProposal>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeResolution<'info> {
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    
+    #[account(mut, address = market.sponsor @ ErrorCode::Unauthorized)]
+    pub sponsor_account: Account<'info, Sponsor>,
+    
+    #[account(
+        mut,
+        seeds = [b"resolution", market.key().as_ref()],
+        bump = proposal.bump,
+        has_one = market,
+        has_one = proposer
+    )]
+    pub proposal: Account<'info, ResolutionProposal>,
+    
+    #[account(mut)]
+    /// CHECK: receives the returned bond, checked by has_one on the proposal
+    pub proposer: UncheckedAccount<'info>,
+    
+    /// Required when the market belongs to a group
+    #[account(mut)]
+    pub market_group: Option<Account<'info, MarketGroup>>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    pub admin: Signer<'info>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    
+    #[account(mut, address = market.sponsor @ ErrorCode::Unauthorized)]
+    pub sponsor_account: Account<'info, Sponsor>,
+    
+    #[account(
+        mut,
+        seeds = [b"resolution", market.key().as_ref()],
+        bump = proposal.bump,
+        has_one = market,
+        has_one = proposer
+    )]
+    pub proposal: Account<'info, ResolutionProposal>,
+    
+    #[account(mut)]
+    /// CHECK: receives the bonds if the proposal is upheld, checked by has_one on the proposal
+    pub proposer: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        constraint = proposal.disputer == Some(disputer.key()) @ ErrorCode::Unauthorized
+    )]
+    /// CHECK: receives the bonds if the proposal is overturned, checked against proposal.disputer
+    pub disputer: UncheckedAccount<'info>,
+    
+    /// Required when the market belongs to a group
+    #[account(mut)]
+    pub market_group: Option<Account<'info, MarketGroup>>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct InitMarketGroup<'info> {
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+    
+    #[account(
+        constraint = sponsor_account.authority == sponsor.key() @ ErrorCode::Unauthorized
+    )]
+    pub sponsor_account: Account<'info, Sponsor>,
+    
+    #[account(
+        init,
+        payer = sponsor,
+        space = MarketGroup::SPACE,
+        seeds = [b"market_group", sponsor_account.key().as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub market_group: Account<'info, MarketGroup>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(address = config.usdc_mint @ ErrorCode::InvalidMint)]
+    pub usdc_mint: Box<Account<'info, Mint>>,
+    
+    #[account(
+        mut,
+        token::mint = usdc_mint,
+        token::authority = sponsor
+    )]
+    pub sponsor_token_account: Box<Account<'info, TokenAccount>>,
+    
+    /// Holds the group collateral, owned by the market group PDA
+    #[account(
+        init,
+        payer = sponsor,
+        token::mint = usdc_mint,
+        token::authority = market_group,
+        seeds = [b"group_vault", market_group.key().as_ref()],
+        bump
+    )]
+    pub group_vault: Box<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositGroupCollateral<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = market_group.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub market_group: Account<'info, MarketGroup>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(address = config.usdc_mint @ ErrorCode::InvalidMint)]
+    pub usdc_mint: Box<Account<'info, Mint>>,
+    
+    #[account(
+        mut,
+        token::mint = usdc_mint,
+        token::authority = authority
+    )]
+    pub authority_token_account: Box<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"group_vault", market_group.key().as_ref()],
+        bump
+    )]
+    pub group_vault: Box<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawGroupCollateral<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = market_group.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub market_group: Account<'info, MarketGroup>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+    
+    #[account(address = config.usdc_mint @ ErrorCode::InvalidMint)]
+    pub usdc_mint: Box<Account<'info, Mint>>,
+    
+    #[account(mut, token::mint = usdc_mint)]
+    pub authority_token_account: Box<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"group_vault", market_group.key().as_ref()],
+        bump
+    )]
+    pub group_vault: Box<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct AddMarketToGroup<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = market_group.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub market_group: Account<'info, MarketGroup>,
+    
+    #[account(
+        seeds = [b"group_vault", market_group.key().as_ref()],
+        bump
+    )]
+    pub group_vault: Box<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = market.sponsor == market_group.sponsor @ ErrorCode::Unauthorized,
+        constraint = market.authority == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub market: Account<'info, Market>,
+}
+
+#[derive(Accounts)]
+pub struct SettleGroupMarket<'info> {
+    #[account(mut)]
+    pub market_group: Account<'info, MarketGroup>,
+    
+    #[account(
+        mut,
+        constraint = market.group == Some(market_group.key()) @ ErrorCode::MarketNotInGroup
+    )]
+    pub market: Account<'info, Market>,
+    
+    #[account(mut, address = market.sponsor @ ErrorCode::Unauthorized)]
+    pub sponsor_account: Account<'info, Sponsor>,
+}
+
+#[derive(Accounts)]
+pub struct MarkMarketInvalid<'info> {
+    pub authority: Signer<'info>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, ProgramConfig>,
+    
+    /// Authorized in the handler: the admin, the market authority, or a multi-signature sponsor approval
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    
+    #[account(mut, address = market.sponsor @ ErrorCode::Unauthorized)]
+    pub sponsor_account: Account<'info, Sponsor>,
+    
+    /// Required when the sponsor is multi-signature and the admin is not the caller
+    #[account(mut)]
+    pub pending_action: Option<Account<'info, PendingAction>>,
+    
+    /// Required when the market belongs to a group
+    #[account(mut)]
+    pub market_group: Option<Account<'info, MarketGroup>>,
+}
+
+#[derive(Accounts)]
+pub struct InitMarketVault<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub market: Box<Account<'info, Market>>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProgramConfig>>,
+    
+    #[account(address = config.usdc_mint @ ErrorCode::InvalidMint)]
+    pub usdc_mint: Box<Account<'info, Mint>>,
+    
+    #[account(
+        init,
+        payer = payer,
+        token::mint = usdc_mint,
+        token::authority = market,
+        seeds = [b"market_vault", market.key().as_ref()],
+        bump
+    )]
+    pub market_vault: Box<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[queue_computation_accounts("reveal_refund", user)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ClaimInvalidRefund<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub market: Box<Account<'info, Market>>,
+    
+    #[account(
+        mut,
+        seeds = [b"user_position", market.key().as_ref(), user.key().as_ref()],
+        bump = user_position.bump,
+        has_one = user,
+        has_one = market
+    )]
+    pub user_position: Box<Account<'info, UserPosition>>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProgramConfig>>,
+
+    #[account(address = config.usdc_mint @ ErrorCode::InvalidMint)]
+    pub usdc_mint: Box<Account<'info, Mint>>,
+    
+    #[account(
+        seeds = [b"market_vault", market.key().as_ref()],
+        bump,
+        token::mint = usdc_mint,
+        token::authority = market
+    )]
+    pub market_vault: Box<Account<'info, TokenAccount>>,
+    
+    /// Receives the refund
+    #[account(
+        token::mint = usdc_mint,
+        token::authority = user
+    )]
+    pub user_token_account: Box<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = user,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_REFUND))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("reveal_refund")]
+#[derive(Accounts)]
+pub struct RevealRefundCallback<'info> {
+    #[account(mut)]
+    pub user_position: Account<'info, UserPosition>,
+    #[account(address = user_position.market)]
+    pub market: Box<Account<'info, Market>>,
+    #[account(
+        mut,
+        seeds = [b"market_vault", market.key().as_ref()],
+        bump
+    )]
+    pub market_vault: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint = usdc_mint,
+        token::authority = user_position.user
+    )]
+    pub user_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(address = market_vault.mint)]
+    pub usdc_mint: Box<Account<'info, Mint>>,
+    pub token_program: Program<'info, Token>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_REFUND))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("reveal_categorical_refund", user)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ClaimCategoricalInvalidRefund<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub market: Box<Account<'info, Market>>,
+    
+    #[account(
+        mut,
+        seeds = [b"categorical_position", market.key().as_ref(), user.key().as_ref()],
+        bump = categorical_position.bump,
+        has_one = user,
+        has_one = market
+    )]
+    pub categorical_position: Box<Account<'info, CategoricalPosition>>,
+    
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Box<Account<'info, ProgramConfig>>,
+
+    #[account(address = config.usdc_mint @ ErrorCode::InvalidMint)]
+    pub usdc_mint: Box<Account<'info, Mint>>,
+    
+    #[account(
+        seeds = [b"market_vault", market.key().as_ref()],
+        bump,
+        token::mint = usdc_mint,
+        token::authority = market
+    )]
+    pub market_vault: Box<Account<'info, TokenAccount>>,
+    
+    /// Receives the refund
+    #[account(
+        token::mint = usdc_mint,
+        token::authority = user
+    )]
+    pub user_token_account: Box<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = user,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_CATEGORICAL_REFUND))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("reveal_categorical_refund")]
+#[derive(Accounts)]
+pub struct RevealCategoricalRefundCallback<'info> {
+    #[account(mut)]
+    pub categorical_position: Account<'info, CategoricalPosition>,
+    #[account(address = categorical_position.market)]
+    pub market: Box<Account<'info, Market>>,
+    #[account(
+        mut,
+        seeds = [b"market_vault", market.key().as_ref()],
+        bump
+    )]
+    pub market_vault: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint = usdc_mint,
+        token::authority = categorical_position.user
+    )]
+    pub user_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(address = market_vault.mint)]
+    pub usdc_mint: Box<Account<'info, Mint>>,
+    pub token_program: Program<'info, Token>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_CATEGORICAL_REFUND))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("commit_research", user)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64, trade_id: u64)]
+pub struct CommitResearch<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub market: Box<Account<'info, Market>>,
+    
+    #[account(
+        seeds = [b"user_position", market.key().as_ref(), user.key().as_ref()],
+        bump = user_position.bump,
+        has_one = user,
+        has_one = market
+    )]
+    pub user_position: Box<Account<'info, UserPosition>>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = ResearchCommitment::SPACE,
+        seeds = [b"research", user_position.key().as_ref(), &trade_id.to_le_bytes()],
+        bump
+    )]
+    pub research_commitment: Box<Account<'info, ResearchCommitment>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = user,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_COMMIT_RESEARCH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("commit_research")]
+#[derive(Accounts)]
+pub struct CommitResearchCallback<'info> {
+    #[account(mut)]
+    pub research_commitment: Account<'info, ResearchCommitment>,
+    #[account(mut)]
+    pub user_position: Account<'info, UserPosition>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_COMMIT_RESEARCH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("view_research", sponsor)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct GetResearchView<'info> {
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+    
+    pub sponsor_account: Box<Account<'info, Sponsor>>,
+    
+    /// Sponsor authority, market authority or the market's operator
+    #[account(
+        mut,
+        constraint = market.sponsor == sponsor_account.key() @ ErrorCode::Unauthorized,
+        constraint = sponsor_account.authority == sponsor.key()
+            || market.can_operate(&sponsor.key()) @ ErrorCode::Unauthorized
+    )]
+    pub market: Box<Account<'info, Market>>,
+    
+    #[account(
+        init_if_needed,
+        payer = sponsor,
+        space = SponsorAccessLog::SPACE,
+        seeds = [b"sponsor_access_log", market.key().as_ref()],
+        bump
+    )]
+    pub sponsor_access_log: Box<Account<'info, SponsorAccessLog>>,
+    
+    /// Sponsor's USDC account paying the view fee, required when the market charges one
+    #[account(mut, token::authority = sponsor)]
+    pub sponsor_token_account: Option<Box<Account<'info, TokenAccount>>>,
+    
+    #[account(mut, seeds = [b"market_vault", market.key().as_ref()], bump)]
+    pub market_vault: Option<Box<Account<'info, TokenAccount>>>,
+    
+    pub usdc_mint: Option<Box<Account<'info, Mint>>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+    
+    #[account(
+        has_one = market @ ErrorCode::Unauthorized
+    )]
+    pub research_commitment: Box<Account<'info, ResearchCommitment>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = sponsor,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_VIEW_RESEARCH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("view_research")]
+#[derive(Accounts)]
+pub struct ViewResearchCallback<'info> {
+    pub research_commitment: Account<'info, ResearchCommitment>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_VIEW_RESEARCH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("reveal_research", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct RevealResearch<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub market: Box<Account<'info, Market>>,
+    
+    #[account(
+        has_one = market @ ErrorCode::Unauthorized
+    )]
+    pub research_commitment: Box<Account<'info, ResearchCommitment>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_RESEARCH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("reveal_research")]
+#[derive(Accounts)]
+pub struct RevealResearchCallback<'info> {
+    #[account(mut)]
+    pub research_commitment: Account<'info, ResearchCommitment>,
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_RESEARCH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("score_position", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ScorePosition<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub market: Box<Account<'info, Market>>,
+    
+    #[account(
+        has_one = market @ ErrorCode::Unauthorized
+    )]
+    pub user_position: Box<Account<'info, UserPosition>>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = TraderProfile::SPACE,
+        seeds = [b"trader_profile", user_position.user.as_ref()],
+        bump
+    )]
+    pub trader_profile: Box<Account<'info, TraderProfile>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SCORE_POSITION))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("score_position")]
+#[derive(Accounts)]
+pub struct ScorePositionCallback<'info> {
+    #[account(mut)]
+    pub user_position: Account<'info, UserPosition>,
+    #[account(mut)]
+    pub trader_profile: Account<'info, TraderProfile>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SCORE_POSITION))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("reveal_attention_hint", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct RevealAttentionHint<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub market: Box<Account<'info, Market>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_ATTENTION_HINT))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("reveal_attention_hint")]
+#[derive(Accounts)]
+pub struct RevealAttentionHintCallback<'info> {
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_ATTENTION_HINT))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("initialize_order_batch", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct InitOrderBatch<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub market: Box<Account<'info, Market>>,
+    
+    #[account(
+        init,
+        payer = payer,
+        space = OrderBatch::SPACE,
+        seeds = [b"order_batch", market.key().as_ref(), &market.batch_epoch.to_le_bytes()],
+        bump
+    )]
+    pub order_batch: Box<Account<'info, OrderBatch>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INITIALIZE_ORDER_BATCH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("initialize_order_batch")]
+#[derive(Accounts)]
+pub struct InitializeOrderBatchCallback<'info> {
+    #[account(mut)]
+    pub order_batch: Account<'info, OrderBatch>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INITIALIZE_ORDER_BATCH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("submit_batch_order", user)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct SubmitBatchOrder<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(mut)]
+    pub market: Box<Account<'info, Market>>,
+    
+    /// Orders can only be filled into an existing position
+    #[account(
+        mut,
+        seeds = [b"user_position", market.key().as_ref(), user.key().as_ref()],
+        bump = user_position.bump,
+        has_one = user,
+        has_one = market
+    )]
+    pub user_position: Box<Account<'info, UserPosition>>,
+    
+    #[account(
+        seeds = [b"order_batch", market.key().as_ref(), &market.batch_epoch.to_le_bytes()],
+        bump = order_batch.bump
+    )]
+    pub order_batch: Box<Account<'info, OrderBatch>>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = BatchOrder::SPACE,
+        seeds = [b"batch_order", order_batch.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub batch_order: Box<Account<'info, BatchOrder>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = user,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_BATCH_ORDER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("submit_batch_order")]
 #[derive(Accounts)]
-#[instruction(question: String)]
-pub struct InitMarket<'info> {
+pub struct SubmitBatchOrderCallback<'info> {
     #[account(mut)]
-    pub sponsor: Signer<'info>,
+    pub batch_order: Account<'info, BatchOrder>,
+    #[account(mut)]
+    pub order_batch: Account<'info, OrderBatch>,
+    #[account(mut)]
+    pub user_position: Account<'info, UserPosition>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_SUBMIT_BATCH_ORDER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("clear_order_batch", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct ClearOrderBatch<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub market: Box<Account<'info, Market>>,
+    
+    #[account(
+        mut,
+        seeds = [b"order_batch", market.key().as_ref(), &market.batch_epoch.to_le_bytes()],
+        bump = order_batch.bump
+    )]
+    pub order_batch: Box<Account<'info, OrderBatch>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CLEAR_ORDER_BATCH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("clear_order_batch")]
+#[derive(Accounts)]
+pub struct ClearOrderBatchCallback<'info> {
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    #[account(mut)]
+    pub order_batch: Account<'info, OrderBatch>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CLEAR_ORDER_BATCH))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("fill_batch_order", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct FillBatchOrder<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(has_one = market @ ErrorCode::Unauthorized)]
+    pub order_batch: Box<Account<'info, OrderBatch>>,
+    
+    pub market: Box<Account<'info, Market>>,
+    
+    #[account(has_one = order_batch @ ErrorCode::Unauthorized)]
+    pub batch_order: Box<Account<'info, BatchOrder>>,
+    
+    #[account(
+        seeds = [b"user_position", market.key().as_ref(), batch_order.user.as_ref()],
+        bump = user_position.bump
+    )]
+    pub user_position: Box<Account<'info, UserPosition>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_FILL_BATCH_ORDER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("fill_batch_order")]
+#[derive(Accounts)]
+pub struct FillBatchOrderCallback<'info> {
+    #[account(mut)]
+    pub user_position: Account<'info, UserPosition>,
+    #[account(mut)]
+    pub batch_order: Account<'info, BatchOrder>,
+    #[account(mut)]
+    pub order_batch: Account<'info, OrderBatch>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_FILL_BATCH_ORDER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("initialize_limit_order_book", payer)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct InitLimitOrderBook<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = market.open_limit_book.is_none() @ ErrorCode::LimitOrdersNotMatched
+    )]
+    pub market: Box<Account<'info, Market>>,
+    
+    /// One book per private window, keyed by the window's start time
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = LimitOrderBook::SPACE,
+        seeds = [b"limit_book", market.key().as_ref(), &market.last_switch_ts.to_le_bytes()],
+        bump
+    )]
+    pub limit_order_book: Box<Account<'info, LimitOrderBook>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = payer,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INITIALIZE_LIMIT_ORDER_BOOK))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
+    
+    pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
+}
+
+#[callback_accounts("initialize_limit_order_book")]
+#[derive(Accounts)]
+pub struct InitializeLimitOrderBookCallback<'info> {
+    #[account(mut)]
+    pub limit_order_book: Account<'info, LimitOrderBook>,
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INITIALIZE_LIMIT_ORDER_BOOK))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("place_limit_order", user)]
+#[derive(Accounts)]
+#[instruction(computation_offset: u64)]
+pub struct PlaceLimitOrder<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(mut)]
+    pub market: Box<Account<'info, Market>>,
     
-    #[account(mut)]
-    pub sponsor_account: Account<'info, Sponsor>,
+    #[account(
+        mut,
+        seeds = [b"user_position", market.key().as_ref(), user.key().as_ref()],
+        bump = user_position.bump,
+        has_one = user,
+        has_one = market
+    )]
+    pub user_position: Box<Account<'info, UserPosition>>,
     
     #[account(
-        init,
-        payer = sponsor,
-        space = 8 + 1 + 32 + 32 + (4 + 200) + 8 + 1 + 8 + 8 + (32 * 4) + 8 + 8 + 8 + 8 + 8 + 16 + 8 + 8 + 8 + 1 + 2,
-        seeds = [b"market", sponsor_account.key().as_ref(), question.as_bytes()],
-        bump
+        mut,
+        constraint = market.open_limit_book == Some(limit_order_book.key()) @ ErrorCode::LimitOrdersMatched
     )]
-    pub market: Account<'info, Market>,
+    pub limit_order_book: Box<Account<'info, LimitOrderBook>>,
+    
+    #[account(
+        init_if_needed,
+        space = 9,
+        payer = user,
+        seeds = [&SIGN_PDA_SEED],
+        bump,
+        address = derive_sign_pda!(),
+    )]
+    pub sign_pda_account: Account<'info, SignerAccount>,
+    
+    #[account(address = derive_mxe_pda!())]
+    pub mxe_account: Account<'info, MXEAccount>,
+    
+    #[account(mut, address = derive_mempool_pda!())]
+    /// CHECK: mempool_account, checked by the arcium program.
+    pub mempool_account: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_execpool_pda!())]
+    /// CHECK: executing_pool, checked by the arcium program.
+    pub executing_pool: UncheckedAccount<'info>,
+    
+    #[account(mut, address = derive_comp_pda!(computation_offset))]
+    /// CHECK: computation_account, checked by the arcium program.
+    pub computation_account: UncheckedAccount<'info>,
+    
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PLACE_LIMIT_ORDER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    
+    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
+    pub cluster_account: Box<Account<'info, Cluster>>,
+    
+    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
+    pub pool_account: Account<'info, FeePool>,
+    
+    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
+    pub clock_account: Account<'info, ClockAccount>,
     
     pub system_program: Program<'info, System>,
+    pub arcium_program: Program<'info, Arcium>,
 }
 
-#[queue_computation_accounts("initialize_market", payer)]
+#[callback_accounts("place_limit_order")]
+#[derive(Accounts)]
+pub struct PlaceLimitOrderCallback<'info> {
+    #[account(mut)]
+    pub limit_order_book: Account<'info, LimitOrderBook>,
+    #[account(mut)]
+    pub user_position: Account<'info, UserPosition>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PLACE_LIMIT_ORDER))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[queue_computation_accounts("match_limit_orders", payer)]
 #[derive(Accounts)]
 #[instruction(computation_offset: u64)]
-pub struct InitMarketEncrypted<'info> {
+pub struct MatchLimitOrders<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     
     #[account(mut)]
-    pub market: Account<'info, Market>,
+    pub market: Box<Account<'info, Market>>,
+    
+    #[account(
+        mut,
+        constraint = market.open_limit_book == Some(limit_order_book.key()) @ ErrorCode::LimitOrdersMatched
+    )]
+    pub limit_order_book: Box<Account<'info, LimitOrderBook>>,
     
     #[account(
         init_if_needed,
@@ -882,11 +7202,11 @@ pub struct InitMarketEncrypted<'info> {
     /// CHECK: computation_account, checked by the arcium program.
     pub computation_account: UncheckedAccount<'info>,
     
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INITIALIZE_MARKET))]
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_MATCH_LIMIT_ORDERS))]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     
     #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    pub cluster_account: Account<'info, Cluster>,
+    pub cluster_account: Box<Account<'info, Cluster>>,
     
     #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
     pub pool_account: Account<'info, FeePool>,
@@ -898,41 +7218,45 @@ pub struct InitMarketEncrypted<'info> {
     pub arcium_program: Program<'info, Arcium>,
 }
 
-#[callback_accounts("initialize_market")]
+#[callback_accounts("match_limit_orders")]
 #[derive(Accounts)]
-pub struct InitializeMarketCallback<'info> {
+pub struct MatchLimitOrdersCallback<'info> {
     #[account(mut)]
     pub market: Account<'info, Market>,
+    #[account(mut)]
+    pub limit_order_book: Account<'info, LimitOrderBook>,
     pub arcium_program: Program<'info, Arcium>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INITIALIZE_MARKET))]
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_MATCH_LIMIT_ORDERS))]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
     /// CHECK: instructions_sysvar, checked by the account constraint
     pub instructions_sysvar: AccountInfo<'info>,
 }
 
-#[queue_computation_accounts("initialize_user_position", user)]
+#[queue_computation_accounts("apply_limit_fill", payer)]
 #[derive(Accounts)]
 #[instruction(computation_offset: u64)]
-pub struct InitUserPosition<'info> {
+pub struct ApplyLimitFill<'info> {
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub payer: Signer<'info>,
     
-    pub market: Account<'info, Market>,
+    pub market: Box<Account<'info, Market>>,
     
     #[account(
-        init,
-        payer = user,
-        space = 8 + 1 + 32 + 32 + (32 * 4) + 16 + 4 + (32 * 10), // Added space for close_records Vec (initial capacity for 10 records)
-        seeds = [b"user_position", market.key().as_ref(), user.key().as_ref()],
-        bump
+        mut,
+        has_one = market @ ErrorCode::Unauthorized
     )]
-    pub user_position: Account<'info, UserPosition>,
+    pub limit_order_book: Box<Account<'info, LimitOrderBook>>,
+    
+    #[account(
+        has_one = market @ ErrorCode::Unauthorized
+    )]
+    pub user_position: Box<Account<'info, UserPosition>>,
     
     #[account(
         init_if_needed,
         space = 9,
-        payer = user,
+        payer = payer,
         seeds = [&SIGN_PDA_SEED],
         bump,
         address = derive_sign_pda!(),
@@ -954,11 +7278,11 @@ pub struct InitUserPosition<'info> {
     /// CHECK: computation_account, checked by the arcium program.
     pub computation_account: UncheckedAccount<'info>,
     
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INITIALIZE_USER_POSITION))]
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_APPLY_LIMIT_FILL))]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     
     #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    pub cluster_account: Account<'info, Cluster>,
+    pub cluster_account: Box<Account<'info, Cluster>>,
     
     #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
     pub pool_account: Account<'info, FeePool>,
@@ -970,33 +7294,56 @@ pub struct InitUserPosition<'info> {
     pub arcium_program: Program<'info, Arcium>,
 }
 
-#[callback_accounts("initialize_user_position")]
+#[callback_accounts("apply_limit_fill")]
 #[derive(Accounts)]
-pub struct InitializeUserPositionCallback<'info> {
+pub struct ApplyLimitFillCallback<'info> {
     #[account(mut)]
     pub user_position: Account<'info, UserPosition>,
+    #[account(mut)]
+    pub limit_order_book: Account<'info, LimitOrderBook>,
     pub arcium_program: Program<'info, Arcium>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INITIALIZE_USER_POSITION))]
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_APPLY_LIMIT_FILL))]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
     /// CHECK: instructions_sysvar, checked by the account constraint
     pub instructions_sysvar: AccountInfo<'info>,
 }
 
-#[queue_computation_accounts("process_private_trade", payer)]
+#[queue_computation_accounts("initialize_position_histogram", sponsor)]
 #[derive(Accounts)]
 #[instruction(computation_offset: u64)]
-pub struct TradePrivate<'info> {
+pub struct StartPositionAggregate<'info> {
     #[account(mut)]
-    pub payer: Signer<'info>,
+    pub sponsor: Signer<'info>,
     
-    #[account(mut)]
-    pub market: Account<'info, Market>,
+    pub sponsor_account: Account<'info, Sponsor>,
+    
+    /// Sponsor authority, market authority or the market's operator
+    #[account(
+        mut,
+        constraint = market.sponsor == sponsor_account.key() @ ErrorCode::Unauthorized,
+        constraint = sponsor_account.authority == sponsor.key()
+            || market.can_operate(&sponsor.key()) @ ErrorCode::Unauthorized
+    )]
+    pub market: Box<Account<'info, Market>>,
+    
+    #[account(
+        init,
+        payer = sponsor,
+        space = PositionAggregate::SPACE,
+        seeds = [
+            b"position_aggregate",
+            market.key().as_ref(),
+            &(market.aggregate_round + 1).to_le_bytes()
+        ],
+        bump
+    )]
+    pub position_aggregate: Box<Account<'info, PositionAggregate>>,
     
     #[account(
         init_if_needed,
         space = 9,
-        payer = payer,
+        payer = sponsor,
         seeds = [&SIGN_PDA_SEED],
         bump,
         address = derive_sign_pda!(),
@@ -1018,11 +7365,11 @@ pub struct TradePrivate<'info> {
     /// CHECK: computation_account, checked by the arcium program.
     pub computation_account: UncheckedAccount<'info>,
     
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PROCESS_PRIVATE_TRADE))]
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INITIALIZE_POSITION_HISTOGRAM))]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     
     #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    pub cluster_account: Account<'info, Cluster>,
+    pub cluster_account: Box<Account<'info, Cluster>>,
     
     #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
     pub pool_account: Account<'info, FeePool>,
@@ -1034,41 +7381,50 @@ pub struct TradePrivate<'info> {
     pub arcium_program: Program<'info, Arcium>,
 }
 
-#[callback_accounts("process_private_trade")]
+#[callback_accounts("initialize_position_histogram")]
 #[derive(Accounts)]
-pub struct ProcessPrivateTradeCallback<'info> {
+pub struct InitializePositionHistogramCallback<'info> {
     #[account(mut)]
-    pub market: Account<'info, Market>,
+    pub position_aggregate: Account<'info, PositionAggregate>,
     pub arcium_program: Program<'info, Arcium>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_PROCESS_PRIVATE_TRADE))]
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_INITIALIZE_POSITION_HISTOGRAM))]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
     /// CHECK: instructions_sysvar, checked by the account constraint
     pub instructions_sysvar: AccountInfo<'info>,
 }
 
-#[queue_computation_accounts("update_user_position", user)]
+#[queue_computation_accounts("add_position_to_histogram", payer)]
 #[derive(Accounts)]
 #[instruction(computation_offset: u64)]
-pub struct UpdateUserPositionPrivate<'info> {
+pub struct AggregatePosition<'info> {
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub payer: Signer<'info>,
     
     pub market: Box<Account<'info, Market>>,
     
+    /// Only the latest round accepts positions
     #[account(
         mut,
-        seeds = [b"user_position", market.key().as_ref(), user.key().as_ref()],
-        bump = user_position.bump,
-        has_one = user,
-        has_one = market
+        seeds = [
+            b"position_aggregate",
+            market.key().as_ref(),
+            &market.aggregate_round.to_le_bytes()
+        ],
+        bump = position_aggregate.bump
+    )]
+    pub position_aggregate: Box<Account<'info, PositionAggregate>>,
+    
+    #[account(
+        mut,
+        has_one = market @ ErrorCode::Unauthorized
     )]
     pub user_position: Box<Account<'info, UserPosition>>,
     
     #[account(
         init_if_needed,
         space = 9,
-        payer = user,
+        payer = payer,
         seeds = [&SIGN_PDA_SEED],
         bump,
         address = derive_sign_pda!(),
@@ -1090,7 +7446,7 @@ pub struct UpdateUserPositionPrivate<'info> {
     /// CHECK: computation_account, checked by the arcium program.
     pub computation_account: UncheckedAccount<'info>,
     
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_UPDATE_USER_POSITION))]
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_ADD_POSITION_TO_HISTOGRAM))]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     
     #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
@@ -1106,41 +7462,68 @@ pub struct UpdateUserPositionPrivate<'info> {
     pub arcium_program: Program<'info, Arcium>,
 }
 
-#[callback_accounts("update_user_position")]
+#[callback_accounts("add_position_to_histogram")]
 #[derive(Accounts)]
-pub struct UpdateUserPositionCallback<'info> {
+pub struct AddPositionToHistogramCallback<'info> {
+    #[account(mut)]
+    pub position_aggregate: Account<'info, PositionAggregate>,
     #[account(mut)]
     pub user_position: Account<'info, UserPosition>,
     pub arcium_program: Program<'info, Arcium>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_UPDATE_USER_POSITION))]
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_ADD_POSITION_TO_HISTOGRAM))]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
     /// CHECK: instructions_sysvar, checked by the account constraint
     pub instructions_sysvar: AccountInfo<'info>,
 }
 
-#[queue_computation_accounts("close_position", user)]
+#[queue_computation_accounts("view_position_histogram", sponsor)]
 #[derive(Accounts)]
 #[instruction(computation_offset: u64)]
-pub struct ClosePositionPrivate<'info> {
+pub struct GetPositionHistogramView<'info> {
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub sponsor: Signer<'info>,
     
-    pub market: Box<Account<'info, Market>>,
+    pub sponsor_account: Account<'info, Sponsor>,
     
+    /// Sponsor authority, market authority or the market's operator
     #[account(
         mut,
-        seeds = [b"user_position", market.key().as_ref(), user.key().as_ref()],
-        bump = user_position.bump,
-        has_one = user,
-        has_one = market
+        constraint = market.sponsor == sponsor_account.key() @ ErrorCode::Unauthorized,
+        constraint = sponsor_account.authority == sponsor.key()
+            || market.can_operate(&sponsor.key()) @ ErrorCode::Unauthorized
     )]
-    pub user_position: Box<Account<'info, UserPosition>>,
+    pub market: Box<Account<'info, Market>>,
+    
+    #[account(
+        init_if_needed,
+        payer = sponsor,
+        space = SponsorAccessLog::SPACE,
+        seeds = [b"sponsor_access_log", market.key().as_ref()],
+        bump
+    )]
+    pub sponsor_access_log: Box<Account<'info, SponsorAccessLog>>,
+    
+    /// Sponsor's USDC account paying the view fee, required when the market charges one
+    #[account(mut, token::authority = sponsor)]
+    pub sponsor_token_account: Option<Box<Account<'info, TokenAccount>>>,
+    
+    #[account(mut, seeds = [b"market_vault", market.key().as_ref()], bump)]
+    pub market_vault: Option<Box<Account<'info, TokenAccount>>>,
+    
+    pub usdc_mint: Option<Box<Account<'info, Mint>>>,
+    
+    pub token_program: Option<Program<'info, Token>>,
+    
+    #[account(
+        has_one = market @ ErrorCode::Unauthorized
+    )]
+    pub position_aggregate: Box<Account<'info, PositionAggregate>>,
     
     #[account(
         init_if_needed,
         space = 9,
-        payer = user,
+        payer = sponsor,
         seeds = [&SIGN_PDA_SEED],
         bump,
         address = derive_sign_pda!(),
@@ -1162,7 +7545,7 @@ pub struct ClosePositionPrivate<'info> {
     /// CHECK: computation_account, checked by the arcium program.
     pub computation_account: UncheckedAccount<'info>,
     
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CLOSE_POSITION))]
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_VIEW_POSITION_HISTOGRAM))]
     pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
     
     #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
@@ -1178,311 +7561,326 @@ pub struct ClosePositionPrivate<'info> {
     pub arcium_program: Program<'info, Arcium>,
 }
 
-#[callback_accounts("close_position")]
+#[callback_accounts("view_position_histogram")]
+#[derive(Accounts)]
+pub struct ViewPositionHistogramCallback<'info> {
+    pub position_aggregate: Account<'info, PositionAggregate>,
+    pub arcium_program: Program<'info, Arcium>,
+    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_VIEW_POSITION_HISTOGRAM))]
+    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
+    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: instructions_sysvar, checked by the account constraint
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimPayout<'info> {
+    #[account(mut)]
+    pub trader: Signer<'info>,
+    
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+}
+
+// ========== Computation Definition Init Accounts ==========
+
+#[init_computation_definition_accounts("initialize_market", payer)]
+#[derive(Accounts)]
+pub struct InitInitializeMarketCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("initialize_user_position", payer)]
+#[derive(Accounts)]
+pub struct InitInitializeUserPositionCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("close_position", payer)]
+#[derive(Accounts)]
+pub struct InitClosePositionCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("reveal_market_state", payer)]
+#[derive(Accounts)]
+pub struct InitRevealMarketStateCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("reveal_user_position", payer)]
+#[derive(Accounts)]
+pub struct InitRevealUserPositionCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("hide_market_state", payer)]
+#[derive(Accounts)]
+pub struct InitHideMarketStateCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("view_market_state", payer)]
 #[derive(Accounts)]
-pub struct ClosePositionCallback<'info> {
+pub struct InitViewMarketStateCompDef<'info> {
     #[account(mut)]
-    pub user_position: Account<'info, UserPosition>,
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    pub comp_def_account: UncheckedAccount<'info>,
     pub arcium_program: Program<'info, Arcium>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_CLOSE_POSITION))]
-    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
-    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
-    /// CHECK: instructions_sysvar, checked by the account constraint
-    pub instructions_sysvar: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
 }
 
-#[queue_computation_accounts("reveal_market_state", payer)]
+#[init_computation_definition_accounts("view_user_position", payer)]
 #[derive(Accounts)]
-#[instruction(computation_offset: u64)]
-pub struct SwitchToPublic<'info> {
+pub struct InitViewUserPositionCompDef<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
-    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
     #[account(mut)]
-    pub market: Account<'info, Market>,
-    
-    #[account(
-        init_if_needed,
-        space = 9,
-        payer = payer,
-        seeds = [&SIGN_PDA_SEED],
-        bump,
-        address = derive_sign_pda!(),
-    )]
-    pub sign_pda_account: Account<'info, SignerAccount>,
-    
-    #[account(address = derive_mxe_pda!())]
-    pub mxe_account: Account<'info, MXEAccount>,
-    
-    #[account(mut, address = derive_mempool_pda!())]
-    /// CHECK: mempool_account, checked by the arcium program.
-    pub mempool_account: UncheckedAccount<'info>,
-    
-    #[account(mut, address = derive_execpool_pda!())]
-    /// CHECK: executing_pool, checked by the arcium program.
-    pub executing_pool: UncheckedAccount<'info>,
-    
-    #[account(mut, address = derive_comp_pda!(computation_offset))]
-    /// CHECK: computation_account, checked by the arcium program.
-    pub computation_account: UncheckedAccount<'info>,
-    
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_MARKET_STATE))]
-    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
-    
-    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    pub cluster_account: Account<'info, Cluster>,
-    
-    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
-    pub pool_account: Account<'info, FeePool>,
-    
-    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
-    pub clock_account: Account<'info, ClockAccount>,
-    
+    /// CHECK: comp_def_account, checked by arcium program.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
     pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("initialize_categorical_market", payer)]
+#[derive(Accounts)]
+pub struct InitInitializeCategoricalMarketCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    pub comp_def_account: UncheckedAccount<'info>,
     pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
 }
 
-#[callback_accounts("reveal_market_state")]
+#[init_computation_definition_accounts("initialize_categorical_position", payer)]
 #[derive(Accounts)]
-pub struct RevealMarketStateCallback<'info> {
+pub struct InitInitializeCategoricalPositionCompDef<'info> {
     #[account(mut)]
-    pub market: Account<'info, Market>,
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    pub comp_def_account: UncheckedAccount<'info>,
     pub arcium_program: Program<'info, Arcium>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_REVEAL_MARKET_STATE))]
-    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
-    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
-    /// CHECK: instructions_sysvar, checked by the account constraint
-    pub instructions_sysvar: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
 }
 
+#[init_computation_definition_accounts("process_categorical_trade", payer)]
 #[derive(Accounts)]
-pub struct TradePublic<'info> {
+pub struct InitProcessCategoricalTradeCompDef<'info> {
     #[account(mut)]
-    pub trader: Signer<'info>,
-    
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
     #[account(mut)]
-    pub market: Account<'info, Market>,
+    /// CHECK: comp_def_account, checked by arcium program.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
 }
 
-#[queue_computation_accounts("hide_market_state", payer)]
+#[init_computation_definition_accounts("reveal_refund", payer)]
 #[derive(Accounts)]
-#[instruction(computation_offset: u64)]
-pub struct SwitchToPrivate<'info> {
+pub struct InitRevealRefundCompDef<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
-    
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
     #[account(mut)]
-    pub market: Account<'info, Market>,
-    
-    #[account(
-        init_if_needed,
-        space = 9,
-        payer = payer,
-        seeds = [&SIGN_PDA_SEED],
-        bump,
-        address = derive_sign_pda!(),
-    )]
-    pub sign_pda_account: Account<'info, SignerAccount>,
-    
-    #[account(address = derive_mxe_pda!())]
-    pub mxe_account: Account<'info, MXEAccount>,
-    
-    #[account(mut, address = derive_mempool_pda!())]
-    /// CHECK: mempool_account, checked by the arcium program.
-    pub mempool_account: UncheckedAccount<'info>,
-    
-    #[account(mut, address = derive_execpool_pda!())]
-    /// CHECK: executing_pool, checked by the arcium program.
-    pub executing_pool: UncheckedAccount<'info>,
-    
-    #[account(mut, address = derive_comp_pda!(computation_offset))]
-    /// CHECK: computation_account, checked by the arcium program.
-    pub computation_account: UncheckedAccount<'info>,
-    
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_HIDE_MARKET_STATE))]
-    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
-    
-    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    pub cluster_account: Account<'info, Cluster>,
-    
-    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
-    pub pool_account: Account<'info, FeePool>,
-    
-    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
-    pub clock_account: Account<'info, ClockAccount>,
-    
+    /// CHECK: comp_def_account, checked by arcium program.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
     pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("reveal_categorical_refund", payer)]
+#[derive(Accounts)]
+pub struct InitRevealCategoricalRefundCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    pub comp_def_account: UncheckedAccount<'info>,
     pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
 }
 
-#[callback_accounts("hide_market_state")]
+#[init_computation_definition_accounts("commit_research", payer)]
 #[derive(Accounts)]
-pub struct HideMarketStateCallback<'info> {
+pub struct InitCommitResearchCompDef<'info> {
     #[account(mut)]
-    pub market: Account<'info, Market>,
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    pub comp_def_account: UncheckedAccount<'info>,
     pub arcium_program: Program<'info, Arcium>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_HIDE_MARKET_STATE))]
-    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
-    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
-    /// CHECK: instructions_sysvar, checked by the account constraint
-    pub instructions_sysvar: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
 }
 
-#[queue_computation_accounts("view_market_state", sponsor)]
+#[init_computation_definition_accounts("view_research", payer)]
 #[derive(Accounts)]
-#[instruction(computation_offset: u64)]
-pub struct GetSponsorView<'info> {
+pub struct InitViewResearchCompDef<'info> {
     #[account(mut)]
-    pub sponsor: Signer<'info>,
-    
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
     #[account(mut)]
-    pub market: Account<'info, Market>,
-    
-    #[account(
-        init_if_needed,
-        space = 9,
-        payer = sponsor,
-        seeds = [&SIGN_PDA_SEED],
-        bump,
-        address = derive_sign_pda!(),
-    )]
-    pub sign_pda_account: Account<'info, SignerAccount>,
-    
-    #[account(address = derive_mxe_pda!())]
-    pub mxe_account: Account<'info, MXEAccount>,
-    
-    #[account(mut, address = derive_mempool_pda!())]
-    /// CHECK: mempool_account, checked by the arcium program.
-    pub mempool_account: UncheckedAccount<'info>,
-    
-    #[account(mut, address = derive_execpool_pda!())]
-    /// CHECK: executing_pool, checked by the arcium program.
-    pub executing_pool: UncheckedAccount<'info>,
-    
-    #[account(mut, address = derive_comp_pda!(computation_offset))]
-    /// CHECK: computation_account, checked by the arcium program.
-    pub computation_account: UncheckedAccount<'info>,
-    
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_VIEW_MARKET_STATE))]
-    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
-    
-    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    pub cluster_account: Account<'info, Cluster>,
-    
-    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
-    pub pool_account: Account<'info, FeePool>,
-    
-    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
-    pub clock_account: Account<'info, ClockAccount>,
-    
+    /// CHECK: comp_def_account, checked by arcium program.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
     pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("reveal_research", payer)]
+#[derive(Accounts)]
+pub struct InitRevealResearchCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    pub comp_def_account: UncheckedAccount<'info>,
     pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
 }
 
-#[callback_accounts("view_market_state")]
+#[init_computation_definition_accounts("score_position", payer)]
 #[derive(Accounts)]
-pub struct ViewMarketStateCallback<'info> {
+pub struct InitScorePositionCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
     #[account(mut)]
-    pub market: Account<'info, Market>,
+    /// CHECK: comp_def_account, checked by arcium program.
+    pub comp_def_account: UncheckedAccount<'info>,
     pub arcium_program: Program<'info, Arcium>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_VIEW_MARKET_STATE))]
-    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
-    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
-    /// CHECK: instructions_sysvar, checked by the account constraint
-    pub instructions_sysvar: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
 }
 
-#[queue_computation_accounts("view_user_position", sponsor)]
+#[init_computation_definition_accounts("reveal_attention_hint", payer)]
 #[derive(Accounts)]
-#[instruction(computation_offset: u64)]
-pub struct GetUserPositionView<'info> {
+pub struct InitRevealAttentionHintCompDef<'info> {
     #[account(mut)]
-    pub sponsor: Signer<'info>,
-    
-    pub market: Account<'info, Market>,
-    
-    #[account(
-        has_one = market
-    )]
-    pub user_position: Account<'info, UserPosition>,
-    
-    #[account(
-        init_if_needed,
-        space = 9,
-        payer = sponsor,
-        seeds = [&SIGN_PDA_SEED],
-        bump,
-        address = derive_sign_pda!(),
-    )]
-    pub sign_pda_account: Account<'info, SignerAccount>,
-    
-    #[account(address = derive_mxe_pda!())]
-    pub mxe_account: Account<'info, MXEAccount>,
-    
-    #[account(mut, address = derive_mempool_pda!())]
-    /// CHECK: mempool_account, checked by the arcium program.
-    pub mempool_account: UncheckedAccount<'info>,
-    
-    #[account(mut, address = derive_execpool_pda!())]
-    /// CHECK: executing_pool, checked by the arcium program.
-    pub executing_pool: UncheckedAccount<'info>,
-    
-    #[account(mut, address = derive_comp_pda!(computation_offset))]
-    /// CHECK: computation_account, checked by the arcium program.
-    pub computation_account: UncheckedAccount<'info>,
-    
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_VIEW_USER_POSITION))]
-    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
-    
-    #[account(mut, address = derive_cluster_pda!(mxe_account, ErrorCode::ClusterNotSet))]
-    pub cluster_account: Account<'info, Cluster>,
-    
-    #[account(mut, address = ARCIUM_FEE_POOL_ACCOUNT_ADDRESS)]
-    pub pool_account: Account<'info, FeePool>,
-    
-    #[account(address = ARCIUM_CLOCK_ACCOUNT_ADDRESS)]
-    pub clock_account: Account<'info, ClockAccount>,
-    
-    pub system_program: Program<'info, System>,
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    pub comp_def_account: UncheckedAccount<'info>,
     pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
 }
 
-#[callback_accounts("view_user_position")]
+#[init_computation_definition_accounts("initialize_order_batch", payer)]
 #[derive(Accounts)]
-pub struct ViewUserPositionCallback<'info> {
+pub struct InitInitializeOrderBatchCompDef<'info> {
     #[account(mut)]
-    pub user_position: Account<'info, UserPosition>,
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    #[account(mut)]
+    /// CHECK: comp_def_account, checked by arcium program.
+    pub comp_def_account: UncheckedAccount<'info>,
     pub arcium_program: Program<'info, Arcium>,
-    #[account(address = derive_comp_def_pda!(COMP_DEF_OFFSET_VIEW_USER_POSITION))]
-    pub comp_def_account: Account<'info, ComputationDefinitionAccount>,
-    #[account(address = ::anchor_lang::solana_program::sysvar::instructions::ID)]
-    /// CHECK: instructions_sysvar, checked by the account constraint
-    pub instructions_sysvar: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
 }
 
+#[init_computation_definition_accounts("submit_batch_order", payer)]
 #[derive(Accounts)]
-pub struct ResolveMarket<'info> {
+pub struct InitSubmitBatchOrderCompDef<'info> {
     #[account(mut)]
-    pub authority: Signer<'info>,
-    
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
     #[account(mut)]
-    pub market: Account<'info, Market>,
+    /// CHECK: comp_def_account, checked by arcium program.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
 }
 
+#[init_computation_definition_accounts("clear_order_batch", payer)]
 #[derive(Accounts)]
-pub struct ClaimPayout<'info> {
+pub struct InitClearOrderBatchCompDef<'info> {
     #[account(mut)]
-    pub trader: Signer<'info>,
-    
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
     #[account(mut)]
-    pub market: Account<'info, Market>,
+    /// CHECK: comp_def_account, checked by arcium program.
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
 }
 
-// ========== Computation Definition Init Accounts ==========
-
-#[init_computation_definition_accounts("initialize_market", payer)]
+#[init_computation_definition_accounts("fill_batch_order", payer)]
 #[derive(Accounts)]
-pub struct InitInitializeMarketCompDef<'info> {
+pub struct InitFillBatchOrderCompDef<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     #[account(mut, address = derive_mxe_pda!())]
@@ -1494,9 +7892,9 @@ pub struct InitInitializeMarketCompDef<'info> {
     pub system_program: Program<'info, System>,
 }
 
-#[init_computation_definition_accounts("initialize_user_position", payer)]
+#[init_computation_definition_accounts("initialize_limit_order_book", payer)]
 #[derive(Accounts)]
-pub struct InitInitializeUserPositionCompDef<'info> {
+pub struct InitInitializeLimitOrderBookCompDef<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     #[account(mut, address = derive_mxe_pda!())]
@@ -1508,9 +7906,9 @@ pub struct InitInitializeUserPositionCompDef<'info> {
     pub system_program: Program<'info, System>,
 }
 
-#[init_computation_definition_accounts("process_private_trade", payer)]
+#[init_computation_definition_accounts("place_limit_order", payer)]
 #[derive(Accounts)]
-pub struct InitProcessPrivateTradeCompDef<'info> {
+pub struct InitPlaceLimitOrderCompDef<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     #[account(mut, address = derive_mxe_pda!())]
@@ -1522,9 +7920,9 @@ pub struct InitProcessPrivateTradeCompDef<'info> {
     pub system_program: Program<'info, System>,
 }
 
-#[init_computation_definition_accounts("update_user_position", payer)]
+#[init_computation_definition_accounts("match_limit_orders", payer)]
 #[derive(Accounts)]
-pub struct InitUpdateUserPositionCompDef<'info> {
+pub struct InitMatchLimitOrdersCompDef<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     #[account(mut, address = derive_mxe_pda!())]
@@ -1536,9 +7934,9 @@ pub struct InitUpdateUserPositionCompDef<'info> {
     pub system_program: Program<'info, System>,
 }
 
-#[init_computation_definition_accounts("close_position", payer)]
+#[init_computation_definition_accounts("apply_limit_fill", payer)]
 #[derive(Accounts)]
-pub struct InitClosePositionCompDef<'info> {
+pub struct InitApplyLimitFillCompDef<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     #[account(mut, address = derive_mxe_pda!())]
@@ -1550,9 +7948,9 @@ pub struct InitClosePositionCompDef<'info> {
     pub system_program: Program<'info, System>,
 }
 
-#[init_computation_definition_accounts("reveal_market_state", payer)]
+#[init_computation_definition_accounts("view_my_position", payer)]
 #[derive(Accounts)]
-pub struct InitRevealMarketStateCompDef<'info> {
+pub struct InitViewMyPositionCompDef<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     #[account(mut, address = derive_mxe_pda!())]
@@ -1564,9 +7962,9 @@ pub struct InitRevealMarketStateCompDef<'info> {
     pub system_program: Program<'info, System>,
 }
 
-#[init_computation_definition_accounts("reveal_user_position", payer)]
+#[init_computation_definition_accounts("view_market_summary", payer)]
 #[derive(Accounts)]
-pub struct InitRevealUserPositionCompDef<'info> {
+pub struct InitViewMarketSummaryCompDef<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     #[account(mut, address = derive_mxe_pda!())]
@@ -1578,9 +7976,9 @@ pub struct InitRevealUserPositionCompDef<'info> {
     pub system_program: Program<'info, System>,
 }
 
-#[init_computation_definition_accounts("hide_market_state", payer)]
+#[init_computation_definition_accounts("initialize_position_histogram", payer)]
 #[derive(Accounts)]
-pub struct InitHideMarketStateCompDef<'info> {
+pub struct InitInitializePositionHistogramCompDef<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     #[account(mut, address = derive_mxe_pda!())]
@@ -1592,9 +7990,9 @@ pub struct InitHideMarketStateCompDef<'info> {
     pub system_program: Program<'info, System>,
 }
 
-#[init_computation_definition_accounts("view_market_state", payer)]
+#[init_computation_definition_accounts("add_position_to_histogram", payer)]
 #[derive(Accounts)]
-pub struct InitViewMarketStateCompDef<'info> {
+pub struct InitAddPositionToHistogramCompDef<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     #[account(mut, address = derive_mxe_pda!())]
@@ -1606,9 +8004,9 @@ pub struct InitViewMarketStateCompDef<'info> {
     pub system_program: Program<'info, System>,
 }
 
-#[init_computation_definition_accounts("view_user_position", payer)]
+#[init_computation_definition_accounts("view_position_histogram", payer)]
 #[derive(Accounts)]
-pub struct InitViewUserPositionCompDef<'info> {
+pub struct InitViewPositionHistogramCompDef<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     #[account(mut, address = derive_mxe_pda!())]
@@ -1639,4 +8037,872 @@ pub enum ErrorCode {
     ClusterNotSet,
     #[msg("Sponsor is not whitelisted")]
     SponsorNotWhitelisted,
+    #[msg("Operation not supported for this market type")]
+    WrongMarketType,
+    #[msg("Number of outcomes is out of range")]
+    InvalidOutcomeCount,
+    #[msg("Outcome index is out of range")]
+    InvalidOutcome,
+    #[msg("Market is already resolved")]
+    MarketResolved,
+    #[msg("Invalid market group configuration")]
+    InvalidGroupConfig,
+    #[msg("Market already belongs to a group")]
+    MarketInGroup,
+    #[msg("Market does not belong to this group")]
+    MarketNotInGroup,
+    #[msg("Market group is full")]
+    GroupFull,
+    #[msg("Market group collateral does not cover its maximum payout")]
+    InsufficientGroupCollateral,
+    #[msg("Market group has already reached its YES count")]
+    GroupCompleted,
+    #[msg("Market group has not reached its YES count yet")]
+    GroupNotCompleted,
+    #[msg("Scalar range lower bound must be below the upper bound")]
+    InvalidScalarRange,
+    #[msg("Invalid program configuration")]
+    InvalidConfig,
+    #[msg("Resolution proposal has already been disputed")]
+    AlreadyDisputed,
+    #[msg("Resolution proposal has not been disputed")]
+    NotDisputed,
+    #[msg("Dispute window has closed")]
+    DisputeWindowClosed,
+    #[msg("Dispute window is still open")]
+    DisputeWindowOpen,
+    #[msg("Market has not been resolved as invalid")]
+    MarketNotInvalid,
+    #[msg("Refund has already been computed for this position")]
+    RefundAlreadyComputed,
+    #[msg("Invalid sponsor signer configuration")]
+    InvalidSignerConfig,
+    #[msg("Sponsor does not use multi-signature approvals")]
+    NotMultisig,
+    #[msg("Action requires an approved pending action")]
+    ApprovalRequired,
+    #[msg("Pending action does not have enough approvals")]
+    InsufficientApprovals,
+    #[msg("Pending action has already been executed")]
+    ActionAlreadyExecuted,
+    #[msg("Signer has already approved this action")]
+    AlreadyApproved,
+    #[msg("Trading is frozen pending resolution")]
+    TradingFrozen,
+    #[msg("Private window has not started")]
+    PrivateWindowNotStarted,
+    #[msg("Sponsor bond is below the required minimum")]
+    InsufficientSponsorBond,
+    #[msg("Sponsor bond unstake is pending")]
+    UnstakePending,
+    #[msg("Sponsor bond unstake has not been requested")]
+    UnstakeNotRequested,
+    #[msg("Sponsor bond unstake cooldown has not elapsed")]
+    UnstakeCooldown,
+    #[msg("Sponsor still has open markets")]
+    SponsorHasOpenMarkets,
+    #[msg("No overturned resolution left to slash")]
+    NothingToSlash,
+    #[msg("Market is not resolved")]
+    MarketNotResolved,
+    #[msg("Research commitment has already been revealed")]
+    ResearchAlreadyRevealed,
+    #[msg("Position has already been scored")]
+    PositionAlreadyScored,
+    #[msg("Attention hint was revealed too recently")]
+    HintTooSoon,
+    #[msg("Order batch has already been cleared")]
+    BatchCleared,
+    #[msg("Order batch has not been cleared")]
+    BatchNotCleared,
+    #[msg("Order batch is not initialized yet")]
+    BatchNotReady,
+    #[msg("Order batch epoch is still collecting orders")]
+    BatchEpochOpen,
+    #[msg("Batch order has already been filled")]
+    OrderAlreadyFilled,
+    #[msg("Limit orders must be matched first")]
+    LimitOrdersNotMatched,
+    #[msg("Limit orders have already been matched")]
+    LimitOrdersMatched,
+    #[msg("Limit order book is not initialized yet")]
+    LimitOrderBookNotReady,
+    #[msg("Limit order book is full")]
+    LimitOrderBookFull,
+    #[msg("Invalid limit order slot")]
+    InvalidLimitOrderSlot,
+    #[msg("Not enough traders for a sponsor position view")]
+    NotEnoughTraders,
+    #[msg("Minimum trader count for sponsor views can only be raised")]
+    ViewThresholdDecrease,
+    #[msg("Position aggregate is not initialized yet")]
+    AggregateNotReady,
+    #[msg("Position is already included in this aggregate")]
+    PositionAlreadyAggregated,
+    #[msg("Sponsor view limit reached for this window")]
+    SponsorViewLimitReached,
+    #[msg("Window schedule must alternate starting with the private window, with at most one final open-ended window")]
+    InvalidWindowSchedule,
+    #[msg("A window switch is already in progress")]
+    TransitionPending,
+    #[msg("Computation output is stale: market state changed since it was queued")]
+    StaleComputation,
+    #[msg("No view fees to withdraw")]
+    NoViewFees,
+    #[msg("Mint does not match the configured USDC mint")]
+    InvalidMint,
+    #[msg("Every market in the group must resolve before its collateral is withdrawn")]
+    GroupNotSettled,
+    #[msg("Only traders holding a position in the market can dispute its resolution")]
+    PositionRequired,
+    #[msg("Research can only be attached to the position's latest trade")]
+    InvalidTradeId,
+    #[msg("Order was not included in the batch clearing")]
+    OrderNotIncluded,
+    #[msg("Order batch clearing is already queued")]
+    BatchClearPending,
+    #[msg("Sponsor views need a minimum trader count of at least one")]
+    InvalidMinViewTraders,
+    #[msg("Limit order book for this window is already initialized")]
+    LimitOrderBookExists,
+    #[msg("A limit order placement is still pending")]
+    LimitOrderPending,
+    #[msg("Order already submitted to this batch")]
+    OrderAlreadySubmitted,
+    #[msg("Research already committed for this trade")]
+    ResearchAlreadyCommitted,
+    #[msg("The market charges a view fee but the USDC fee accounts were not provided")]
+    ViewFeeAccountsMissing,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A binary market in its private window, with every optional field empty.
+    fn test_market() -> Market {
+        Market {
+            bump: 255,
+            sponsor: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            resolution_date: 1_000_000,
+            window_state: MarketWindow::Private,
+            liquidity_cap: 10_000,
+            initial_liquidity_usdc: 1_000,
+            market_state: [[0u8; 32]; MARKET_STATE_ENCRYPTED_FIELDS],
+            question: String::new(),
+            public_yes_pool: 0,
+            public_no_pool: 0,
+            public_yes_price: 500,
+            public_no_price: 500,
+            public_total_trades: 0,
+            nonce: 0,
+            opp_window_duration: 3_600,
+            pub_window_duration: 600,
+            last_switch_ts: 0,
+            resolved: false,
+            outcome: None,
+            invalid: false,
+            market_type: MarketType::Binary,
+            num_outcomes: 2,
+            group: None,
+            scalar_lower: 0,
+            scalar_upper: 0,
+            scalar_value: None,
+            long_payout: 0,
+            trading_frozen: false,
+            pending_authority: None,
+            operator: None,
+            created_ts: 0,
+            attention_hint: None,
+            last_hint_ts: 0,
+            batch_epoch: 0,
+            open_limit_book: None,
+            window_traders: 0,
+            min_view_traders: 1,
+            position_count: 0,
+            aggregate_round: 0,
+            public_sponsor_views: 0,
+            max_views_per_window: 0,
+            view_fee_usdc: 0,
+            window_sponsor_views: 0,
+            view_fees_collected: 0,
+            window_schedule: vec![],
+            schedule_index: 0,
+            pending_transition: false,
+            state_version: 0,
+            failed_computations: 0,
+            last_failed_computation: None,
+            last_failure_ts: 0,
+            private_window_opened: false,
+        }
+    }
+
+    #[test]
+    fn market_state_offset_matches_layout() {
+        let mut market_state = [[0u8; 32]; MARKET_STATE_ENCRYPTED_FIELDS];
+        for (i, ct) in market_state.iter_mut().enumerate() {
+            *ct = [i as u8 + 1; 32];
+        }
+        // The offset must not depend on how long the question is
+        for question in ["", "Will it rain?", &"x".repeat(200)] {
+            let market = Market {
+                market_state,
+                question: question.to_string(),
+                window_schedule: vec![
+                    WindowScheduleEntry {
+                        window: MarketWindow::Private,
+                        duration: 600,
+                    };
+                    MAX_WINDOW_SCHEDULE_ENTRIES
+                ],
+                outcome: Some(OUTCOME_YES),
+                group: Some(Pubkey::new_unique()),
+                scalar_value: Some(1),
+                pending_authority: Some(Pubkey::new_unique()),
+                operator: Some(Pubkey::new_unique()),
+                attention_hint: Some(AttentionLevel::High),
+                open_limit_book: Some(Pubkey::new_unique()),
+                last_failed_computation: Some(ComputationKind::PrivateTrade),
+                ..test_market()
+            };
+
+            let mut data = Vec::new();
+            market.try_serialize(&mut data).unwrap();
+            assert!(data.len() <= Market::SPACE);
+
+            let start = MARKET_STATE_OFFSET as usize;
+            let end = start + MARKET_STATE_LEN as usize;
+            assert_eq!(&data[start..end], market_state.concat().as_slice());
+        }
+    }
+
+    #[test]
+    fn user_position_state_offset_matches_layout() {
+        let mut position_state = [[0u8; 32]; USER_POSITION_ENCRYPTED_FIELDS];
+        for (i, ct) in position_state.iter_mut().enumerate() {
+            *ct = [i as u8 + 1; 32];
+        }
+        let position = UserPosition {
+            bump: 255,
+            user: Pubkey::new_unique(),
+            market: Pubkey::new_unique(),
+            position_state,
+            nonce: 7,
+            refund_usdc: None,
+            refund_pending: false,
+            scored: false,
+            last_window_ts: 0,
+            aggregate_round: 0,
+            failed_updates: 0,
+            last_failure_ts: 0,
+            trade_count: 0,
+            last_trade_ts: 0,
+            state_version: 0,
+            close_records: vec![],
+        };
+
+        let mut data = Vec::new();
+        position.try_serialize(&mut data).unwrap();
+
+        let start = USER_POSITION_STATE_OFFSET as usize;
+        let end = start + USER_POSITION_STATE_LEN as usize;
+        assert_eq!(&data[start..end], position_state.concat().as_slice());
+    }
+
+    #[test]
+    fn categorical_state_offset_matches_layout() {
+        let mut state = [[0u8; 32]; CATEGORICAL_STATE_ENCRYPTED_FIELDS];
+        for (i, ct) in state.iter_mut().enumerate() {
+            *ct = [i as u8 + 1; 32];
+        }
+        let categorical_state = CategoricalState {
+            bump: 255,
+            market: Pubkey::new_unique(),
+            state,
+            nonce: 7,
+        };
+
+        let mut data = Vec::new();
+        categorical_state.try_serialize(&mut data).unwrap();
+
+        let start = CATEGORICAL_STATE_OFFSET as usize;
+        let end = start + CATEGORICAL_STATE_LEN as usize;
+        assert_eq!(&data[start..end], state.concat().as_slice());
+    }
+
+    #[test]
+    fn research_note_state_offset_matches_layout() {
+        let note_state = [[1u8; 32], [2u8; 32]];
+        let research_commitment = ResearchCommitment {
+            bump: 255,
+            user_position: Pubkey::new_unique(),
+            market: Pubkey::new_unique(),
+            user: Pubkey::new_unique(),
+            trade_id: 3,
+            committed_ts: 5,
+            note_state,
+            nonce: 7,
+            revealed_hash: None,
+        };
+
+        let mut data = Vec::new();
+        research_commitment.try_serialize(&mut data).unwrap();
+
+        let start = RESEARCH_NOTE_STATE_OFFSET as usize;
+        let end = start + RESEARCH_NOTE_STATE_LEN as usize;
+        assert_eq!(&data[start..end], note_state.concat().as_slice());
+        assert!(data.len() <= ResearchCommitment::SPACE);
+    }
+
+    #[test]
+    fn order_batch_state_offsets_match_layout() {
+        let totals_state = [[1u8; 32], [2u8; 32]];
+        let prices_state = [[3u8; 32], [4u8; 32]];
+        let order_batch = OrderBatch {
+            bump: 255,
+            market: Pubkey::new_unique(),
+            epoch: 1,
+            opened_ts: 2,
+            order_count: 3,
+            filled_count: 4,
+            cleared: true,
+            totals_state,
+            totals_nonce: 5,
+            prices_state,
+            prices_nonce: 6,
+            clearing: false,
+        };
+
+        let mut data = Vec::new();
+        order_batch.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), OrderBatch::SPACE);
+
+        let len = ORDER_BATCH_STATE_LEN as usize;
+        let totals = ORDER_BATCH_TOTALS_OFFSET as usize;
+        let prices = ORDER_BATCH_PRICES_OFFSET as usize;
+        assert_eq!(&data[totals..totals + len], totals_state.concat().as_slice());
+        assert_eq!(&data[prices..prices + len], prices_state.concat().as_slice());
+
+        let order_state = [[7u8; 32], [8u8; 32]];
+        let batch_order = BatchOrder {
+            bump: 255,
+            order_batch: Pubkey::new_unique(),
+            user: Pubkey::new_unique(),
+            filled: false,
+            order_state,
+            nonce: 9,
+            pending: false,
+        };
+
+        let mut data = Vec::new();
+        batch_order.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), BatchOrder::SPACE);
+
+        let start = BATCH_ORDER_STATE_OFFSET as usize;
+        let end = start + BATCH_ORDER_STATE_LEN as usize;
+        assert_eq!(&data[start..end], order_state.concat().as_slice());
+    }
+
+    #[test]
+    fn limit_order_book_state_offsets_match_layout() {
+        let mut orders_state = [[0u8; 32]; LIMIT_ORDER_BOOK_ENCRYPTED_FIELDS];
+        for (i, ct) in orders_state.iter_mut().enumerate() {
+            *ct = [i as u8 + 1; 32];
+        }
+        let mut fills_state = [[0u8; 32]; LIMIT_FILLS_ENCRYPTED_FIELDS];
+        for (i, ct) in fills_state.iter_mut().enumerate() {
+            *ct = [i as u8 + 101; 32];
+        }
+        let limit_order_book = LimitOrderBook {
+            bump: 255,
+            market: Pubkey::new_unique(),
+            owners: [Pubkey::new_unique(); MAX_LIMIT_ORDERS],
+            order_count: 3,
+            matched: true,
+            applied: [false; MAX_LIMIT_ORDERS],
+            orders_state,
+            orders_nonce: 5,
+            fills_state,
+            fills_nonce: 6,
+            pending_slot: Some(2),
+        };
+
+        let mut data = Vec::new();
+        limit_order_book.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), LimitOrderBook::SPACE);
+
+        let orders = LIMIT_ORDER_BOOK_OFFSET as usize;
+        let fills = LIMIT_FILLS_OFFSET as usize;
+        assert_eq!(
+            &data[orders..orders + LIMIT_ORDER_BOOK_LEN as usize],
+            orders_state.concat().as_slice()
+        );
+        assert_eq!(
+            &data[fills..fills + LIMIT_FILLS_LEN as usize],
+            fills_state.concat().as_slice()
+        );
+    }
+
+    #[test]
+    fn position_aggregate_state_offset_matches_layout() {
+        let mut histogram_state = [[0u8; 32]; POSITION_HISTOGRAM_ENCRYPTED_FIELDS];
+        for (i, ct) in histogram_state.iter_mut().enumerate() {
+            *ct = [i as u8 + 1; 32];
+        }
+        let position_aggregate = PositionAggregate {
+            bump: 255,
+            market: Pubkey::new_unique(),
+            round: 2,
+            positions_included: 9,
+            histogram_state,
+            nonce: 4,
+        };
+
+        let mut data = Vec::new();
+        position_aggregate.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), PositionAggregate::SPACE);
+
+        let start = POSITION_AGGREGATE_STATE_OFFSET as usize;
+        let end = start + POSITION_AGGREGATE_STATE_LEN as usize;
+        assert_eq!(&data[start..end], histogram_state.concat().as_slice());
+    }
+
+    #[test]
+    fn sponsor_access_log_keeps_counting_after_wrapping() {
+        let mut log = SponsorAccessLog {
+            bump: 255,
+            market: Pubkey::new_unique(),
+            market_state_views: 0,
+            market_summary_views: 0,
+            position_views: 0,
+            histogram_views: 0,
+            research_views: 0,
+            entries: vec![],
+        };
+        let viewer = Pubkey::new_unique();
+        for i in 0..(MAX_ACCESS_LOG_ENTRIES + 3) {
+            let kind = if i % 2 == 0 {
+                SponsorViewKind::MarketState
+            } else {
+                SponsorViewKind::Position
+            };
+            log.record(SponsorAccessEntry {
+                viewer,
+                kind,
+                target: Pubkey::new_unique(),
+                timestamp: i as i64,
+            });
+        }
+
+        assert_eq!(log.total_views() as usize, MAX_ACCESS_LOG_ENTRIES + 3);
+        assert_eq!(log.market_state_views, 18);
+        assert_eq!(log.position_views, 17);
+        assert_eq!(log.entries.len(), MAX_ACCESS_LOG_ENTRIES);
+        // The oldest entries were overwritten by the newest ones
+        assert_eq!(log.entries[0].timestamp, MAX_ACCESS_LOG_ENTRIES as i64);
+        assert_eq!(log.entries[2].timestamp, MAX_ACCESS_LOG_ENTRIES as i64 + 2);
+        assert_eq!(log.entries[3].timestamp, 3);
+
+        let mut data = Vec::new();
+        log.try_serialize(&mut data).unwrap();
+        assert!(data.len() <= SponsorAccessLog::SPACE);
+    }
+
+    #[test]
+    fn window_schedule_must_alternate_from_private() {
+        let entry = |window, duration| WindowScheduleEntry { window, duration };
+
+        assert!(validate_window_schedule(&[]).is_ok());
+        assert!(validate_window_schedule(&[
+            entry(MarketWindow::Private, 86_400),
+            entry(MarketWindow::Public, 600),
+            entry(MarketWindow::Private, 3_600),
+            entry(MarketWindow::Public, 0),
+        ])
+        .is_ok());
+
+        // Markets open private
+        assert!(validate_window_schedule(&[entry(MarketWindow::Public, 600)]).is_err());
+        // Windows must alternate
+        assert!(validate_window_schedule(&[
+            entry(MarketWindow::Private, 600),
+            entry(MarketWindow::Private, 600),
+        ])
+        .is_err());
+        // Only the last window may be open-ended
+        assert!(validate_window_schedule(&[
+            entry(MarketWindow::Private, 0),
+            entry(MarketWindow::Public, 600),
+        ])
+        .is_err());
+        let too_long: Vec<_> = (0..=MAX_WINDOW_SCHEDULE_ENTRIES)
+            .map(|i| {
+                let window = if i % 2 == 0 {
+                    MarketWindow::Private
+                } else {
+                    MarketWindow::Public
+                };
+                entry(window, 600)
+            })
+            .collect();
+        assert!(validate_window_schedule(&too_long).is_err());
+    }
+
+    fn test_bond(amount: u64) -> SponsorBond {
+        SponsorBond {
+            bump: 255,
+            sponsor: Pubkey::new_unique(),
+            amount,
+            unstake_requested_ts: None,
+            slash_count: 0,
+            total_slashed: 0,
+        }
+    }
+
+    #[test]
+    fn sponsor_bond_cooldown_runs_from_unstake_request() {
+        let mut bond = test_bond(1_000);
+        assert!(!bond.cooldown_elapsed(i64::MAX, 0));
+
+        bond.unstake_requested_ts = Some(1_000);
+        assert!(!bond.cooldown_elapsed(1_599, 600));
+        assert!(bond.cooldown_elapsed(1_600, 600));
+        assert!(bond.cooldown_elapsed(1_000, 0));
+        // A cooldown past the end of time never elapses
+        assert!(!bond.cooldown_elapsed(i64::MAX - 1, i64::MAX));
+    }
+
+    #[test]
+    fn sponsor_bond_slashes_once_per_overturned_resolution() {
+        let mut bond = test_bond(1_000);
+        assert_eq!(bond.apply_slash(400, 0), None);
+
+        assert_eq!(bond.apply_slash(400, 2), Some(400));
+        assert_eq!(bond.amount, 600);
+        // The second slash is capped at what is left in the bond
+        assert_eq!(bond.apply_slash(800, 2), Some(600));
+        assert_eq!(bond.amount, 0);
+        assert_eq!(bond.apply_slash(100, 2), None);
+
+        assert_eq!(bond.slash_count, 2);
+        assert_eq!(bond.total_slashed, 1_000);
+    }
+
+    #[test]
+    fn scalar_payout_is_clamped_to_the_range() {
+        let mut market = test_market();
+        market.market_type = MarketType::Scalar;
+        market.scalar_lower = 1_000_000;
+        market.scalar_upper = 5_000_000;
+
+        assert_eq!(market.scalar_long_payout(1_000_000), 0);
+        assert_eq!(market.scalar_long_payout(2_000_000), 250);
+        assert_eq!(market.scalar_long_payout(5_000_000), 1000);
+        // Values outside the range pay fully short or fully long
+        assert_eq!(market.scalar_long_payout(0), 0);
+        assert_eq!(market.scalar_long_payout(u64::MAX), 1000);
+    }
+
+    #[test]
+    fn market_group_completes_after_max_yes() {
+        let mut group = MarketGroup {
+            bump: 255,
+            sponsor: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            name: "First two".to_string(),
+            max_yes: 2,
+            collateral_usdc: 0,
+            max_liquidity_cap: 0,
+            markets: vec![Pubkey::new_unique(); 4],
+            resolved_yes: 0,
+            resolved_count: 0,
+            completed: false,
+        };
+
+        assert_eq!(group.record_outcome(OUTCOME_NO), Some(false));
+        assert_eq!(group.record_outcome(OUTCOME_YES), Some(false));
+        assert_eq!(group.record_outcome(OUTCOME_YES), Some(true));
+        assert!(group.completed);
+        // The remaining markets can only resolve NO
+        assert_eq!(group.record_outcome(OUTCOME_YES), None);
+        assert_eq!(group.record_outcome(OUTCOME_NO), Some(false));
+
+        assert_eq!(group.resolved_yes, 2);
+        assert_eq!(group.resolved_count, 4);
+    }
+
+    #[test]
+    fn sponsor_ledger_counts_resolutions_by_outcome() {
+        let mut sponsor = Sponsor {
+            bump: 255,
+            authority: Pubkey::new_unique(),
+            name: "Sponsor".to_string(),
+            is_whitelisted: true,
+            creation_date: 0,
+            total_markets_created: 5,
+            signers: vec![],
+            threshold: 0,
+            markets_resolved: 0,
+            markets_resolved_yes: 0,
+            markets_resolved_no: 0,
+            markets_resolved_invalid: 0,
+            resolutions_disputed: 0,
+            resolutions_overturned: 0,
+            total_resolution_secs: 0,
+            total_liquidity_provided: 0,
+        };
+        let mut market = test_market();
+        market.created_ts = 1_000;
+
+        market.outcome = Some(OUTCOME_YES);
+        assert!(sponsor.record_resolution(&market, 2_000).is_some());
+        market.outcome = Some(OUTCOME_NO);
+        assert!(sponsor.record_resolution(&market, 4_000).is_some());
+        market.outcome = None;
+        market.invalid = true;
+        assert!(sponsor.record_resolution(&market, 1_500).is_some());
+        // Scalar resolutions only count towards the totals
+        market.invalid = false;
+        market.market_type = MarketType::Scalar;
+        // A clock behind the creation time adds nothing
+        assert!(sponsor.record_resolution(&market, 500).is_some());
+
+        assert_eq!(sponsor.markets_resolved, 4);
+        assert_eq!(sponsor.markets_resolved_yes, 1);
+        assert_eq!(sponsor.markets_resolved_no, 1);
+        assert_eq!(sponsor.markets_resolved_invalid, 1);
+        assert_eq!(sponsor.total_resolution_secs, 1_000 + 3_000 + 500);
+        assert_eq!(sponsor.average_resolution_secs(), 1_125);
+        assert_eq!(sponsor.open_markets(), 1);
+
+        sponsor.total_resolution_secs = u64::MAX;
+        assert!(sponsor.record_resolution(&market, 2_000).is_none());
+    }
+
+    #[test]
+    fn trader_profile_weights_brier_by_stake() {
+        let mut profile = TraderProfile {
+            bump: 255,
+            wallet: Pubkey::new_unique(),
+            positions_scored: 0,
+            total_staked_usdc: 0,
+            total_payout_usdc: 0,
+            realized_pnl: 0,
+            brier_weighted_sum: 0,
+            brier_weight: 0,
+        };
+        assert_eq!(profile.average_brier(), 0);
+
+        assert!(profile.record_score(300, 500, 100).is_some());
+        assert!(profile.record_score(100, 0, 900).is_some());
+
+        assert_eq!(profile.positions_scored, 2);
+        assert_eq!(profile.total_staked_usdc, 400);
+        assert_eq!(profile.total_payout_usdc, 500);
+        assert_eq!(profile.realized_pnl, 100);
+        // (100 * 300 + 900 * 100) / 400
+        assert_eq!(profile.average_brier(), 300);
+
+        profile.brier_weight = u64::MAX;
+        assert!(profile.record_score(1, 0, 0).is_none());
+    }
+
+    #[test]
+    fn attention_hints_are_spaced_by_the_interval() {
+        let mut market = test_market();
+        assert!(market.attention_hint_due(ATTENTION_HINT_INTERVAL));
+
+        market.last_hint_ts = 10_000;
+        assert!(!market.attention_hint_due(10_000 + ATTENTION_HINT_INTERVAL - 1));
+        assert!(market.attention_hint_due(10_000 + ATTENTION_HINT_INTERVAL));
+
+        assert!(AttentionLevel::from_bucket(0) == AttentionLevel::Low);
+        assert!(AttentionLevel::from_bucket(1) == AttentionLevel::Medium);
+        assert!(AttentionLevel::from_bucket(2) == AttentionLevel::High);
+        assert!(AttentionLevel::from_bucket(u8::MAX) == AttentionLevel::High);
+    }
+
+    fn test_limit_order_book() -> LimitOrderBook {
+        LimitOrderBook {
+            bump: 255,
+            market: Pubkey::new_unique(),
+            owners: [Pubkey::default(); MAX_LIMIT_ORDERS],
+            order_count: 0,
+            matched: false,
+            applied: [false; MAX_LIMIT_ORDERS],
+            orders_state: [[0u8; 32]; LIMIT_ORDER_BOOK_ENCRYPTED_FIELDS],
+            orders_nonce: 1,
+            fills_state: [[0u8; 32]; LIMIT_FILLS_ENCRYPTED_FIELDS],
+            fills_nonce: 0,
+            pending_slot: None,
+        }
+    }
+
+    #[test]
+    fn limit_order_slots_fill_in_order_until_the_book_is_full() {
+        let mut book = test_limit_order_book();
+        let owners: Vec<Pubkey> = (0..MAX_LIMIT_ORDERS).map(|_| Pubkey::new_unique()).collect();
+
+        for (i, owner) in owners.iter().enumerate() {
+            assert_eq!(book.reserve_slot(*owner), Some(i as u8));
+            assert_eq!(book.pending_slot, Some(i as u8));
+            book.pending_slot = None;
+        }
+        assert_eq!(book.order_count as usize, MAX_LIMIT_ORDERS);
+        assert_eq!(book.owners.to_vec(), owners);
+        assert_eq!(book.reserve_slot(Pubkey::new_unique()), None);
+    }
+
+    #[test]
+    fn failed_limit_order_placement_frees_its_slot() {
+        let mut book = test_limit_order_book();
+        let placed = Pubkey::new_unique();
+        assert_eq!(book.reserve_slot(placed), Some(0));
+        book.release_pending_slot(true);
+        assert_eq!(book.pending_slot, None);
+
+        assert_eq!(book.reserve_slot(Pubkey::new_unique()), Some(1));
+        book.release_pending_slot(false);
+        assert_eq!(book.pending_slot, None);
+        assert_eq!(book.order_count, 1);
+        assert_eq!(book.owners[0], placed);
+        assert_eq!(book.owners[1], Pubkey::default());
+
+        // The freed slot is handed out again
+        let retried = Pubkey::new_unique();
+        assert_eq!(book.reserve_slot(retried), Some(1));
+        assert_eq!(book.owners[1], retried);
+
+        // Nothing pending leaves the book alone
+        book.release_pending_slot(true);
+        book.release_pending_slot(false);
+        assert_eq!(book.order_count, 2);
+    }
+
+    #[test]
+    fn sponsor_views_stop_at_the_window_limit() {
+        let mut market = test_market();
+        // No limit configured
+        market.window_sponsor_views = u32::MAX - 1;
+        assert!(market.sponsor_view_available());
+
+        market.max_views_per_window = 2;
+        market.window_sponsor_views = 0;
+        assert!(market.sponsor_view_available());
+        market.window_sponsor_views = 1;
+        assert!(market.sponsor_view_available());
+        market.window_sponsor_views = 2;
+        assert!(!market.sponsor_view_available());
+    }
+
+    #[test]
+    fn state_version_rejects_stale_output() {
+        let mut market = test_market();
+        assert!(advance_state_version(&mut market, 0));
+        assert_eq!(market.state_version, 1);
+        // Output computed before the first write landed
+        assert!(!advance_state_version(&mut market, 0));
+        assert_eq!(market.state_version, 1);
+        assert!(advance_state_version(&mut market, 1));
+        assert_eq!(market.state_version, 2);
+    }
+
+    /// An empty position that has never traded.
+    fn test_position() -> UserPosition {
+        UserPosition {
+            bump: 255,
+            user: Pubkey::new_unique(),
+            market: Pubkey::new_unique(),
+            position_state: [[0u8; 32]; USER_POSITION_ENCRYPTED_FIELDS],
+            nonce: 0,
+            refund_usdc: None,
+            refund_pending: false,
+            scored: false,
+            last_window_ts: 0,
+            aggregate_round: 0,
+            failed_updates: 0,
+            last_failure_ts: 0,
+            trade_count: 0,
+            last_trade_ts: 0,
+            state_version: 0,
+            close_records: vec![],
+        }
+    }
+
+    #[test]
+    fn window_traders_count_each_trader_once_per_private_window() {
+        let mut market = test_market();
+        market.last_switch_ts = 1_000;
+        let mut first = test_position();
+        let mut second = test_position();
+
+        market.record_window_trader(&mut first);
+        market.record_window_trader(&mut first);
+        market.record_window_trader(&mut second);
+        assert_eq!(market.window_traders, 2);
+        assert_eq!(first.last_window_ts, 1_000);
+
+        // Public trades don't count
+        market.window_state = MarketWindow::Public;
+        market.last_switch_ts = 2_000;
+        market.window_traders = 0;
+        market.record_window_trader(&mut first);
+        assert_eq!(market.window_traders, 0);
+
+        // The next private window counts the same trader again
+        market.window_state = MarketWindow::Private;
+        market.last_switch_ts = 3_000;
+        market.record_window_trader(&mut first);
+        assert_eq!(market.window_traders, 1);
+    }
+
+    #[test]
+    fn position_version_rejects_stale_output() {
+        let mut position = test_position();
+        position.state_version = 3;
+        assert!(!advance_position_version(&mut position, 2));
+        assert_eq!(position.state_version, 3);
+        assert!(advance_position_version(&mut position, 3));
+        assert_eq!(position.state_version, 4);
+
+        position.record_failure(50);
+        position.record_failure(60);
+        assert_eq!(position.failed_updates, 2);
+        assert_eq!(position.last_failure_ts, 60);
+    }
+
+    #[test]
+    fn categorical_position_version_rejects_stale_output() {
+        let mut position = CategoricalPosition {
+            bump: 0,
+            user: Pubkey::new_unique(),
+            market: Pubkey::new_unique(),
+            position_state: [[0; 32]; CATEGORICAL_POSITION_ENCRYPTED_FIELDS],
+            nonce: 0,
+            refund_usdc: None,
+            refund_pending: false,
+            failed_updates: 0,
+            last_failure_ts: 0,
+            state_version: 1,
+        };
+        assert!(!advance_categorical_position_version(&mut position, 0));
+        assert_eq!(position.state_version, 1);
+        assert!(advance_categorical_position_version(&mut position, 1));
+        assert_eq!(position.state_version, 2);
+    }
+
+    #[test]
+    fn market_failures_keep_the_latest_computation() {
+        let mut market = test_market();
+        market.record_failure(ComputationKind::PrivateTrade, 100);
+        market.record_failure(ComputationKind::ClearOrderBatch, 200);
+
+        assert_eq!(market.failed_computations, 2);
+        assert_eq!(market.last_failed_computation, Some(ComputationKind::ClearOrderBatch));
+        assert_eq!(market.last_failure_ts, 200);
+        // Recording a failure leaves the state untouched for a retry
+        assert_eq!(market.state_version, 0);
+        assert!(market.window_state == MarketWindow::Private);
+    }
 }
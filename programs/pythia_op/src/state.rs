@@ -1,5 +1,105 @@
 use anchor_lang::prelude::*;
 
+/// Number of 32-byte ciphertexts in the encrypted `MarketState` circuit struct
+pub const MARKET_STATE_ENCRYPTED_FIELDS: usize = 7;
+/// Number of 32-byte ciphertexts in the encrypted `UserPosition` circuit struct
+pub const USER_POSITION_ENCRYPTED_FIELDS: usize = 5;
+
+/// Maximum number of outcomes in a categorical market (matches `MAX_OUTCOMES` in the circuits)
+pub const MAX_OUTCOMES: usize = 8;
+/// Number of 32-byte ciphertexts in the encrypted `CategoricalMarketState` circuit struct (pools + total_trades)
+pub const CATEGORICAL_STATE_ENCRYPTED_FIELDS: usize = MAX_OUTCOMES + 1;
+/// Number of 32-byte ciphertexts in the encrypted `CategoricalPosition` circuit struct (tokens + net_usdc)
+pub const CATEGORICAL_POSITION_ENCRYPTED_FIELDS: usize = MAX_OUTCOMES + 1;
+
+/// Maximum number of markets linked in a single `MarketGroup`
+pub const MAX_GROUP_MARKETS: usize = 32;
+
+/// Outcome indices of a binary market, matching the yes_pool/no_pool order
+pub const OUTCOME_YES: u8 = 0;
+pub const OUTCOME_NO: u8 = 1;
+
+/// Byte offset of `UserPosition.position_state`: 8 (discriminator) + 1 (bump) + 32 (user) + 32 (market)
+pub const USER_POSITION_STATE_OFFSET: u32 = 8 + 1 + 32 + 32;
+/// Byte length of `UserPosition.position_state`
+pub const USER_POSITION_STATE_LEN: u32 = 32 * USER_POSITION_ENCRYPTED_FIELDS as u32;
+/// Byte offset of `Market.market_state`: 8 (discriminator) + 1 (bump) + 32 (sponsor) + 32 (authority)
+/// + 8 (resolution_date) + 1 (window_state) + 8 (liquidity_cap) + 8 (initial_liquidity_usdc)
+pub const MARKET_STATE_OFFSET: u32 = 8 + 1 + 32 + 32 + 8 + 1 + 8 + 8;
+/// Byte length of `Market.market_state`
+pub const MARKET_STATE_LEN: u32 = 32 * MARKET_STATE_ENCRYPTED_FIELDS as u32;
+/// Byte offset of `CategoricalState.state`: 8 (discriminator) + 1 (bump) + 32 (market)
+pub const CATEGORICAL_STATE_OFFSET: u32 = 8 + 1 + 32;
+/// Byte length of `CategoricalState.state`
+pub const CATEGORICAL_STATE_LEN: u32 = 32 * CATEGORICAL_STATE_ENCRYPTED_FIELDS as u32;
+/// Byte offset of `CategoricalPosition.position_state`: 8 (discriminator) + 1 (bump) + 32 (user) + 32 (market)
+pub const CATEGORICAL_POSITION_STATE_OFFSET: u32 = 8 + 1 + 32 + 32;
+/// Byte length of `CategoricalPosition.position_state`
+pub const CATEGORICAL_POSITION_STATE_LEN: u32 = 32 * CATEGORICAL_POSITION_ENCRYPTED_FIELDS as u32;
+
+/// Number of 32-byte ciphertexts in the encrypted `ResearchNote` circuit struct
+pub const RESEARCH_NOTE_ENCRYPTED_FIELDS: usize = 2;
+/// Byte offset of `ResearchCommitment.note_state`: 8 (discriminator) + 1 (bump) + 32 (user_position)
+/// + 32 (market) + 32 (user) + 8 (trade_id) + 8 (committed_ts)
+pub const RESEARCH_NOTE_STATE_OFFSET: u32 = 8 + 1 + 32 + 32 + 32 + 8 + 8;
+/// Byte length of `ResearchCommitment.note_state`
+pub const RESEARCH_NOTE_STATE_LEN: u32 = 32 * RESEARCH_NOTE_ENCRYPTED_FIELDS as u32;
+
+/// Number of 32-byte ciphertexts in the encrypted `TradeInput` circuit struct
+pub const TRADE_INPUT_ENCRYPTED_FIELDS: usize = 2;
+/// Number of 32-byte ciphertexts in the encrypted `BatchTotals` and `ClearingPrices` circuit structs
+pub const BATCH_ENCRYPTED_FIELDS: usize = 2;
+/// Byte offset of `OrderBatch.totals_state`: 8 (discriminator) + 1 (bump) + 32 (market) + 8 (epoch)
+/// + 8 (opened_ts) + 4 (order_count) + 4 (filled_count) + 1 (cleared)
+pub const ORDER_BATCH_TOTALS_OFFSET: u32 = 8 + 1 + 32 + 8 + 8 + 4 + 4 + 1;
+/// Byte length of `OrderBatch.totals_state` and `OrderBatch.prices_state`
+pub const ORDER_BATCH_STATE_LEN: u32 = 32 * BATCH_ENCRYPTED_FIELDS as u32;
+/// Byte offset of `OrderBatch.prices_state`: totals_state followed by its 16-byte nonce
+pub const ORDER_BATCH_PRICES_OFFSET: u32 = ORDER_BATCH_TOTALS_OFFSET + ORDER_BATCH_STATE_LEN + 16;
+/// Byte offset of `BatchOrder.order_state`: 8 (discriminator) + 1 (bump) + 32 (order_batch) + 32 (user) + 1 (filled)
+pub const BATCH_ORDER_STATE_OFFSET: u32 = 8 + 1 + 32 + 32 + 1;
+/// Byte length of `BatchOrder.order_state`
+pub const BATCH_ORDER_STATE_LEN: u32 = 32 * TRADE_INPUT_ENCRYPTED_FIELDS as u32;
+/// Minimum seconds an order batch collects orders before it can be cleared
+pub const BATCH_EPOCH_DURATION: i64 = 300;
+
+/// Number of 32-byte ciphertexts in the encrypted `MarketSummary` circuit struct
+pub const MARKET_SUMMARY_ENCRYPTED_FIELDS: usize = 7;
+/// Number of 32-byte ciphertexts in the encrypted `PositionHistogram` circuit struct
+pub const POSITION_HISTOGRAM_ENCRYPTED_FIELDS: usize = 7;
+/// Byte offset of `PositionAggregate.histogram_state`: 8 (discriminator) + 1 (bump) + 32 (market)
+/// + 8 (round) + 4 (positions_included)
+pub const POSITION_AGGREGATE_STATE_OFFSET: u32 = 8 + 1 + 32 + 8 + 4;
+/// Byte length of `PositionAggregate.histogram_state`
+pub const POSITION_AGGREGATE_STATE_LEN: u32 = 32 * POSITION_HISTOGRAM_ENCRYPTED_FIELDS as u32;
+/// Most recent sponsor view requests kept in a `SponsorAccessLog`
+pub const MAX_ACCESS_LOG_ENTRIES: usize = 32;
+/// Entries allowed in a market's window schedule
+pub const MAX_WINDOW_SCHEDULE_ENTRIES: usize = 8;
+/// Slots in a market's `LimitOrderBook` per private window (matches `MAX_LIMIT_ORDERS` in the circuits)
+pub const MAX_LIMIT_ORDERS: usize = 8;
+/// Number of 32-byte ciphertexts in the encrypted `LimitOrderInput` circuit struct
+pub const LIMIT_ORDER_INPUT_ENCRYPTED_FIELDS: usize = 4;
+/// Number of 32-byte ciphertexts in the encrypted `LimitOrderBook` circuit struct
+pub const LIMIT_ORDER_BOOK_ENCRYPTED_FIELDS: usize = 4 * MAX_LIMIT_ORDERS;
+/// Number of 32-byte ciphertexts in the encrypted `LimitFills` circuit struct
+pub const LIMIT_FILLS_ENCRYPTED_FIELDS: usize = 2 * MAX_LIMIT_ORDERS;
+/// Byte offset of `LimitOrderBook.orders_state`: 8 (discriminator) + 1 (bump) + 32 (market)
+/// + 32 * MAX_LIMIT_ORDERS (owners) + 1 (order_count) + 1 (matched) + MAX_LIMIT_ORDERS (applied)
+pub const LIMIT_ORDER_BOOK_OFFSET: u32 = 8 + 1 + 32 + 32 * MAX_LIMIT_ORDERS as u32 + 1 + 1 + MAX_LIMIT_ORDERS as u32;
+/// Byte length of `LimitOrderBook.orders_state`
+pub const LIMIT_ORDER_BOOK_LEN: u32 = 32 * LIMIT_ORDER_BOOK_ENCRYPTED_FIELDS as u32;
+/// Byte offset of `LimitOrderBook.fills_state`: orders_state followed by its 16-byte nonce
+pub const LIMIT_FILLS_OFFSET: u32 = LIMIT_ORDER_BOOK_OFFSET + LIMIT_ORDER_BOOK_LEN + 16;
+/// Byte length of `LimitOrderBook.fills_state`
+pub const LIMIT_FILLS_LEN: u32 = 32 * LIMIT_FILLS_ENCRYPTED_FIELDS as u32;
+
+/// Minimum seconds between two attention hints on the same market
+pub const ATTENTION_HINT_INTERVAL: i64 = 3600;
+
+/// Maximum number of signer keys on a multi-signature sponsor
+pub const MAX_SPONSOR_SIGNERS: usize = 10;
+
 #[account]
 pub struct Sponsor {
     pub bump: u8,
@@ -8,6 +108,157 @@ pub struct Sponsor {
     pub is_whitelisted: bool,
     pub creation_date: i64,
     pub total_markets_created: u64,
+    /// Optional M-of-N signer set; privileged actions need `threshold` approvals
+    /// through a `PendingAction` when `threshold > 0`
+    pub signers: Vec<Pubkey>,  // Max MAX_SPONSOR_SIGNERS entries
+    pub threshold: u8,
+    // Reputation ledger, updated as the sponsor's markets resolve
+    pub markets_resolved: u64,
+    pub markets_resolved_yes: u64,
+    pub markets_resolved_no: u64,
+    pub markets_resolved_invalid: u64,
+    pub resolutions_disputed: u64,
+    pub resolutions_overturned: u64,
+    /// Sum of (resolution time - market creation time) over resolved markets
+    pub total_resolution_secs: u64,
+    pub total_liquidity_provided: u64,
+}
+
+impl Sponsor {
+    pub const SPACE: usize = 8 + 1 + 32 + (4 + 100) + 1 + 8 + 8 + (4 + 32 * MAX_SPONSOR_SIGNERS) + 1 + 8 * 8;
+
+    pub fn open_markets(&self) -> u64 {
+        self.total_markets_created.saturating_sub(self.markets_resolved)
+    }
+
+    pub fn average_resolution_secs(&self) -> u64 {
+        self.total_resolution_secs.checked_div(self.markets_resolved).unwrap_or(0)
+    }
+
+    /// Adds a just-resolved market to the reputation ledger, or returns `None` if the
+    /// resolution time total would overflow
+    pub fn record_resolution(&mut self, market: &Market, now: i64) -> Option<()> {
+        self.markets_resolved += 1;
+        if market.invalid {
+            self.markets_resolved_invalid += 1;
+        } else if market.market_type == MarketType::Binary {
+            match market.outcome {
+                Some(OUTCOME_YES) => self.markets_resolved_yes += 1,
+                Some(_) => self.markets_resolved_no += 1,
+                None => {}
+            }
+        }
+
+        let time_to_resolution = now.saturating_sub(market.created_ts).max(0) as u64;
+        self.total_resolution_secs = self.total_resolution_secs.checked_add(time_to_resolution)?;
+        Some(())
+    }
+
+    pub fn is_multisig(&self) -> bool {
+        self.threshold > 0
+    }
+
+    pub fn is_signer(&self, key: &Pubkey) -> bool {
+        self.signers.contains(key)
+    }
+}
+
+/// Privileged sponsor action awaiting M-of-N approval
+#[account]
+pub struct PendingAction {
+    pub bump: u8,
+    pub sponsor: Pubkey,
+    pub action_id: u64,
+    pub action: SponsorAction,
+    pub approvals: Vec<Pubkey>,  // Max MAX_SPONSOR_SIGNERS entries
+    pub created_ts: i64,
+    pub executed: bool,
+}
+
+impl PendingAction {
+    pub const SPACE: usize = 8 + 1 + 32 + 8 + SponsorAction::SPACE + (4 + 32 * MAX_SPONSOR_SIGNERS) + 8 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum SponsorAction {
+    /// Create a market whose question hashes to `question_hash`
+    CreateMarket { question_hash: [u8; 32] },
+    /// Propose `outcome` (or `value` for scalar markets) as the resolution of `market`
+    ResolveMarket { market: Pubkey, outcome: u8, value: u64 },
+    /// Replace the signer set with keys hashing to `signers_hash` and a new threshold
+    SetSigners { signers_hash: [u8; 32], threshold: u8 },
+    /// Offer the authority of `market` to `new_authority`
+    TransferMarketAuthority { market: Pubkey, new_authority: Pubkey },
+    /// Set or clear the delegated operator of `market`
+    SetMarketOperator { market: Pubkey, operator: Option<Pubkey> },
+    /// Raise the positions required before the sponsor may view positions in `market`
+    SetMinViewTraders { market: Pubkey, min_view_traders: u32 },
+    /// Resolve `market` as invalid
+    MarkMarketInvalid { market: Pubkey },
+}
+
+impl SponsorAction {
+    // Largest variant: SetMarketOperator
+    pub const SPACE: usize = 1 + 32 + (1 + 32);
+}
+
+/// Program-wide settings, owned by the admin who handles whitelisting and disputes
+#[account]
+pub struct ProgramConfig {
+    pub bump: u8,
+    pub admin: Pubkey,
+    /// Seconds after a resolution proposal during which traders can dispute it
+    pub dispute_period: i64,
+    /// Bond in lamports posted by the proposer, and matched by a disputer
+    pub resolution_bond: u64,
+    /// Minimum USDC a sponsor must keep staked in its `SponsorBond` to create markets
+    pub min_sponsor_bond: u64,
+    /// Seconds between requesting an unstake and withdrawing the sponsor bond
+    pub unstake_cooldown: i64,
+    /// Mint of the USDC held in group collateral and sponsor bond vaults
+    pub usdc_mint: Pubkey,
+}
+
+impl ProgramConfig {
+    pub const SPACE: usize = 8 + 1 + 32 + 8 + 8 + 8 + 8 + 32;
+}
+
+/// Slashable stake a sponsor posts before creating markets, held as USDC in the bond vault
+/// token account owned by this PDA
+#[account]
+pub struct SponsorBond {
+    pub bump: u8,
+    pub sponsor: Pubkey,
+    /// USDC staked in the bond vault
+    pub amount: u64,
+    /// Set by `request_sponsor_unstake`; staking again or creating a market is blocked until withdrawn
+    pub unstake_requested_ts: Option<i64>,
+    /// Number of overturned resolutions already slashed, compared against the sponsor's ledger
+    pub slash_count: u64,
+    pub total_slashed: u64,
+}
+
+impl SponsorBond {
+    pub const SPACE: usize = 8 + 1 + 32 + 8 + (1 + 8) + 8 + 8;
+
+    /// Whether a requested unstake has waited out `cooldown` seconds at `now`
+    pub fn cooldown_elapsed(&self, now: i64, cooldown: i64) -> bool {
+        self.unstake_requested_ts
+            .is_some_and(|requested_ts| now >= requested_ts.saturating_add(cooldown))
+    }
+
+    /// Takes up to `amount` from the bond for the next unslashed overturned resolution and
+    /// returns the USDC to pay out, or `None` once every overturned resolution has been slashed.
+    pub fn apply_slash(&mut self, amount: u64, resolutions_overturned: u64) -> Option<u64> {
+        if self.slash_count >= resolutions_overturned {
+            return None;
+        }
+        let slashed = amount.min(self.amount);
+        self.amount -= slashed;
+        self.slash_count += 1;
+        self.total_slashed = self.total_slashed.saturating_add(slashed);
+        Some(slashed)
+    }
 }
 
 #[account]
@@ -15,13 +266,16 @@ pub struct Market {
     pub bump: u8,
     pub sponsor: Pubkey,  // Reference to Sponsor account
     pub authority: Pubkey,
-    pub question: String,
     pub resolution_date: i64,
     pub window_state: MarketWindow,
     pub liquidity_cap: u64,
     pub initial_liquidity_usdc: u64,  // Track initial sponsor liquidity
-    /// Encrypted market state: [yes_pool, no_pool, k, total_trades] as 32-byte ciphertexts
-    pub market_state: [[u8; 32]; 4],
+    /// Encrypted market state: [yes_pool, no_pool, k, total_trades, window_trades, window_volume_usdc,
+    /// window_yes_volume_usdc]
+    /// as 32-byte ciphertexts
+    pub market_state: [[u8; 32]; MARKET_STATE_ENCRYPTED_FIELDS],
+    /// Kept after `market_state`: Borsh writes it at its actual length, so anything after it moves
+    pub question: String,
     pub public_yes_pool: u64,
     pub public_no_pool: u64,
     pub public_yes_price: u64,   // Price in thousandths (0-1000, where 500 = 50%)
@@ -32,7 +286,213 @@ pub struct Market {
     pub pub_window_duration: u64,
     pub last_switch_ts: i64,
     pub resolved: bool,
-    pub outcome: Option<bool>,
+    /// Winning outcome index (`OUTCOME_YES`/`OUTCOME_NO` for binary markets)
+    pub outcome: Option<u8>,
+    /// Resolved as invalid: no outcome pays, traders are refunded their net USDC instead
+    pub invalid: bool,
+    pub market_type: MarketType,
+    /// Number of outcomes: 2 for binary markets, 2..=MAX_OUTCOMES for categorical markets
+    pub num_outcomes: u8,
+    /// `MarketGroup` this market belongs to, if any
+    pub group: Option<Pubkey>,
+    /// Range of a scalar market; long pays in full at or above `scalar_upper`
+    pub scalar_lower: u64,
+    pub scalar_upper: u64,
+    /// Value a scalar market resolved to
+    pub scalar_value: Option<u64>,
+    /// Payout per long share in thousandths (0-1000), short receives 1000 - long_payout
+    pub long_payout: u64,
+    /// Set once a sponsor resolves early; trading and window switches are rejected
+    pub trading_frozen: bool,
+    /// New authority awaiting `accept_market_authority`
+    pub pending_authority: Option<Pubkey>,
+    /// Delegated key for day-to-day operations (sponsor views); cannot resolve the market
+    pub operator: Option<Pubkey>,
+    pub created_ts: i64,
+    /// Latest coarse activity level of the private window, if one has been revealed
+    pub attention_hint: Option<AttentionLevel>,
+    pub last_hint_ts: i64,
+    /// Epoch of the `OrderBatch` currently collecting private orders
+    pub batch_epoch: u64,
+    /// Limit order book of the current private window; must be matched before switching to public
+    pub open_limit_book: Option<Pubkey>,
+    /// Distinct traders since the current private window opened
+    pub window_traders: u32,
+    /// Staked positions required before the sponsor may view individual positions or an aggregate; only ever raised
+    pub min_view_traders: u32,
+    /// User positions opened on this market
+    pub position_count: u32,
+    /// Round of the latest `PositionAggregate`
+    pub aggregate_round: u64,
+    /// Sponsor view requests on this market as of the latest public reveal
+    pub public_sponsor_views: u32,
+    /// Sponsor view requests allowed per window, 0 for unlimited
+    pub max_views_per_window: u32,
+    /// USDC the sponsor pays into the market vault per view request, 0 for free
+    pub view_fee_usdc: u64,
+    /// Sponsor view requests since the current window opened
+    pub window_sponsor_views: u32,
+    /// View fees held in the market vault until the admin withdraws them
+    pub view_fees_collected: u64,
+    /// Windows to run before falling back to alternating `opp_window_duration` / `pub_window_duration`
+    pub window_schedule: Vec<WindowScheduleEntry>,
+    /// Schedule entry describing the current window; past the end once the schedule is exhausted
+    pub schedule_index: u8,
+    /// Set while a window switch is queued and cleared by its callback
+    pub pending_transition: bool,
+    /// Bumped on every write of `market_state`; computations carry the version they read
+    pub state_version: u64,
+    /// Market computations whose callback reported a failure
+    pub failed_computations: u32,
+    pub last_failed_computation: Option<ComputationKind>,
+    pub last_failure_ts: i64,
+    /// Set once the encrypted market state exists and a private window has actually opened
+    pub private_window_opened: bool,
+}
+
+impl Market {
+    pub const SPACE: usize = 8 // discriminator
+        + 1 // bump
+        + 32 // sponsor
+        + 32 // authority
+        + 8 // resolution_date
+        + 1 // window_state
+        + 8 // liquidity_cap
+        + 8 // initial_liquidity_usdc
+        + (32 * MARKET_STATE_ENCRYPTED_FIELDS) // market_state
+        + (4 + 200) // question
+        + 8 + 8 + 8 + 8 + 8 // public pools, prices and total_trades
+        + 16 // nonce
+        + 8 + 8 + 8 // window durations and last_switch_ts
+        + 1 // resolved
+        + 2 // outcome
+        + 1 // invalid
+        + 1 // market_type
+        + 1 // num_outcomes
+        + (1 + 32) // group
+        + 8 + 8 // scalar_lower, scalar_upper
+        + (1 + 8) // scalar_value
+        + 8 // long_payout
+        + 1 // trading_frozen
+        + (1 + 32) // pending_authority
+        + (1 + 32) // operator
+        + 8 // created_ts
+        + (1 + 1) // attention_hint
+        + 8 // last_hint_ts
+        + 8 // batch_epoch
+        + (1 + 32) // open_limit_book
+        + 4 // window_traders
+        + 4 + 4 // min_view_traders, position_count
+        + 8 // aggregate_round
+        + 4 // public_sponsor_views
+        + 4 + 8 + 4 // max_views_per_window, view_fee_usdc, window_sponsor_views
+        + 8 // view_fees_collected
+        + (4 + MAX_WINDOW_SCHEDULE_ENTRIES * WindowScheduleEntry::SPACE) // window_schedule
+        + 1 // schedule_index
+        + 1 // pending_transition
+        + 8 // state_version
+        + 4 // failed_computations
+        + (1 + 1) // last_failed_computation
+        + 8 // last_failure_ts
+        + 1; // private_window_opened
+
+    /// Market authority or its delegated operator
+    pub fn can_operate(&self, key: &Pubkey) -> bool {
+        self.authority == *key || self.operator == Some(*key)
+    }
+
+    /// Binary and scalar markets share the encrypted yes/no (long/short) pools
+    pub fn has_binary_pools(&self) -> bool {
+        matches!(self.market_type, MarketType::Binary | MarketType::Scalar)
+    }
+
+    /// Length of the current window in seconds, or `None` for a final window that never switches
+    pub fn current_window_duration(&self) -> Option<u64> {
+        match self.window_schedule.get(self.schedule_index as usize) {
+            Some(entry) if entry.duration == 0 => None,
+            Some(entry) => Some(entry.duration),
+            None => Some(match self.window_state {
+                MarketWindow::Private => self.opp_window_duration,
+                MarketWindow::Public => self.pub_window_duration,
+            }),
+        }
+    }
+
+    pub fn window_expired(&self, now: i64) -> bool {
+        self.current_window_duration()
+            .is_some_and(|duration| now >= self.last_switch_ts + duration as i64)
+    }
+
+    /// Moves to the next schedule entry once a window switch lands
+    pub fn advance_window_schedule(&mut self) {
+        if (self.schedule_index as usize) < self.window_schedule.len() {
+            self.schedule_index += 1;
+        }
+    }
+
+    /// Long-token payout per 1000 for a scalar resolution; values outside the range pay
+    /// fully long or fully short
+    pub fn scalar_long_payout(&self, value: u64) -> u64 {
+        let clamped = value.clamp(self.scalar_lower, self.scalar_upper);
+        ((clamped - self.scalar_lower) as u128 * 1000
+            / (self.scalar_upper - self.scalar_lower) as u128) as u64
+    }
+
+    /// Whether the sponsor has views left in the current window; `max_views_per_window == 0`
+    /// means unlimited
+    pub fn sponsor_view_available(&self) -> bool {
+        self.max_views_per_window == 0 || self.window_sponsor_views < self.max_views_per_window
+    }
+
+    /// Whether `ATTENTION_HINT_INTERVAL` has passed since the last attention hint
+    pub fn attention_hint_due(&self, now: i64) -> bool {
+        now >= self.last_hint_ts.saturating_add(ATTENTION_HINT_INTERVAL)
+    }
+
+    pub fn record_failure(&mut self, computation: ComputationKind, now: i64) {
+        self.failed_computations += 1;
+        self.last_failed_computation = Some(computation);
+        self.last_failure_ts = now;
+    }
+
+    /// Counts a trader towards `window_traders` on their first trade in the current private window
+    pub fn record_window_trader(&mut self, user_position: &mut UserPosition) {
+        if self.window_state == MarketWindow::Private
+            && user_position.last_window_ts != self.last_switch_ts
+        {
+            user_position.last_window_ts = self.last_switch_ts;
+            self.window_traders += 1;
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum MarketType {
+    Binary,
+    /// Categorical markets keep their encrypted pools in a separate `CategoricalState` account
+    /// and trade only in the private window
+    Categorical,
+    /// Scalar markets trade long (yes pool) vs short (no pool) and resolve to a value in
+    /// `[scalar_lower, scalar_upper]`
+    Scalar,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AttentionLevel {
+    Low,
+    Medium,
+    High,
+}
+
+impl AttentionLevel {
+    /// Maps the bucket revealed by the `reveal_attention_hint` circuit
+    pub fn from_bucket(bucket: u8) -> Self {
+        match bucket {
+            0 => AttentionLevel::Low,
+            1 => AttentionLevel::Medium,
+            _ => AttentionLevel::High,
+        }
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
@@ -41,18 +501,425 @@ pub enum MarketWindow {
     Public,
 }
 
+/// Computation that can fail after being queued, recorded so the failure is visible on-chain
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ComputationKind {
+    PrivateTrade,
+    RevealMarketState,
+    HideMarketState,
+    ClearOrderBatch,
+    MatchLimitOrders,
+    PositionUpdate,
+    ClosePosition,
+    BatchFill,
+    LimitFill,
+    LimitBookInit,
+    LimitOrderPlacement,
+    BatchSubmit,
+    PositionAggregate,
+    Research,
+    Refund,
+    Score,
+    AttentionHint,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct WindowScheduleEntry {
+    pub window: MarketWindow,
+    /// Seconds; 0 keeps the market in this window until resolution
+    pub duration: u64,
+}
+
+impl WindowScheduleEntry {
+    pub const SPACE: usize = 1 + 8;
+}
+
 #[account]
 pub struct UserPosition {
     pub bump: u8,
     pub user: Pubkey,
     pub market: Pubkey,
-    /// Encrypted user position: [yes_tokens, no_tokens, yes_tokens_closed, no_tokens_closed] as 32-byte ciphertexts
-    pub position_state: [[u8; 32]; 4],
+    /// Encrypted user position: [yes_tokens, no_tokens, yes_tokens_closed, no_tokens_closed, net_usdc] as 32-byte ciphertexts
+    pub position_state: [[u8; 32]; USER_POSITION_ENCRYPTED_FIELDS],
     pub nonce: u128,
+    /// USDC paid back to the trader from the market vault once an invalid market's refund has been computed
+    pub refund_usdc: Option<u64>,
+    /// A refund computation is queued; further claims are rejected until its callback lands
+    pub refund_pending: bool,
+    /// Set once the position has been added to the trader's `TraderProfile`
+    pub scored: bool,
+    /// `Market.last_switch_ts` of the private window the trader last traded in
+    pub last_window_ts: i64,
+    /// Latest `PositionAggregate` round this position was folded into
+    pub aggregate_round: u64,
+    /// Position computations whose callback reported a failure
+    pub failed_updates: u32,
+    pub last_failure_ts: i64,
+    /// Trades applied to this position; research commitments reference the latest by this id
+    pub trade_count: u64,
+    /// Time the latest trade was applied to the position
+    pub last_trade_ts: i64,
+    /// Bumped on every `position_state` write so callbacks can reject output computed from an older position
+    pub state_version: u64,
     /// Track position close events for public window reveal
     pub close_records: Vec<CloseRecord>,
 }
 
+impl UserPosition {
+    /// Numbers a trade once it has been applied to the encrypted position
+    pub fn record_trade(&mut self, now: i64) {
+        self.trade_count += 1;
+        self.last_trade_ts = now;
+    }
+
+    pub fn record_failure(&mut self, now: i64) {
+        self.failed_updates += 1;
+        self.last_failure_ts = now;
+    }
+}
+
+/// Per-market record of every sponsor view request, so traders can see when and how often
+/// the sponsor looked
+#[account]
+pub struct SponsorAccessLog {
+    pub bump: u8,
+    pub market: Pubkey,
+    pub market_state_views: u32,
+    pub market_summary_views: u32,
+    pub position_views: u32,
+    pub histogram_views: u32,
+    pub research_views: u32,
+    /// Ring buffer of the most recent `MAX_ACCESS_LOG_ENTRIES` requests
+    pub entries: Vec<SponsorAccessEntry>,
+}
+
+impl SponsorAccessLog {
+    pub const SPACE: usize = 8 + 1 + 32 + 4 * 5 + 4 + MAX_ACCESS_LOG_ENTRIES * SponsorAccessEntry::SPACE;
+
+    pub fn total_views(&self) -> u32 {
+        self.market_state_views
+            + self.market_summary_views
+            + self.position_views
+            + self.histogram_views
+            + self.research_views
+    }
+
+    /// Counts the request and stores it, overwriting the oldest entry once the buffer is full
+    pub fn record(&mut self, entry: SponsorAccessEntry) {
+        let slot = self.total_views() as usize % MAX_ACCESS_LOG_ENTRIES;
+        match entry.kind {
+            SponsorViewKind::MarketState => self.market_state_views += 1,
+            SponsorViewKind::MarketSummary => self.market_summary_views += 1,
+            SponsorViewKind::Position => self.position_views += 1,
+            SponsorViewKind::PositionHistogram => self.histogram_views += 1,
+            SponsorViewKind::Research => self.research_views += 1,
+        }
+        if self.entries.len() < MAX_ACCESS_LOG_ENTRIES {
+            self.entries.push(entry);
+        } else {
+            self.entries[slot] = entry;
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SponsorAccessEntry {
+    pub viewer: Pubkey,
+    pub kind: SponsorViewKind,
+    /// Account that was viewed: the market, a user position, an aggregate or a research commitment
+    pub target: Pubkey,
+    pub timestamp: i64,
+}
+
+impl SponsorAccessEntry {
+    pub const SPACE: usize = 32 + 1 + 32 + 8;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SponsorViewKind {
+    MarketState,
+    MarketSummary,
+    Position,
+    PositionHistogram,
+    Research,
+}
+
+/// Encrypted histogram of a market's positions, built one position at a time so the
+/// sponsor can see the distribution without any individual position
+#[account]
+pub struct PositionAggregate {
+    pub bump: u8,
+    pub market: Pubkey,
+    pub round: u64,
+    /// Positions folded into the histogram, staked or not
+    pub positions_included: u32,
+    /// Encrypted histogram: [staked_positions, size_buckets[0..4], yes_holders, no_holders] as 32-byte ciphertexts
+    pub histogram_state: [[u8; 32]; POSITION_HISTOGRAM_ENCRYPTED_FIELDS],
+    pub nonce: u128,
+}
+
+impl PositionAggregate {
+    pub const SPACE: usize = POSITION_AGGREGATE_STATE_OFFSET as usize + POSITION_AGGREGATE_STATE_LEN as usize + 16;
+}
+
+/// Encrypted limit orders placed during one private window, matched against the pool
+/// just before the market switches to public
+#[account]
+pub struct LimitOrderBook {
+    pub bump: u8,
+    pub market: Pubkey,
+    /// Trader owning each slot; slots below `order_count` are taken
+    pub owners: [Pubkey; MAX_LIMIT_ORDERS],
+    pub order_count: u8,
+    pub matched: bool,
+    /// Whether each slot's fill has been written to its owner's position
+    pub applied: [bool; MAX_LIMIT_ORDERS],
+    /// Encrypted orders: is_buy, is_yes, size and limit_price columns as 32-byte ciphertexts
+    pub orders_state: [[u8; 32]; LIMIT_ORDER_BOOK_ENCRYPTED_FIELDS],
+    pub orders_nonce: u128,
+    /// Encrypted fills: shares and usdc columns, set when the book is matched
+    pub fills_state: [[u8; 32]; LIMIT_FILLS_ENCRYPTED_FIELDS],
+    pub fills_nonce: u128,
+    /// Slot reserved by the placement awaiting its callback; placements run one at a time
+    pub pending_slot: Option<u8>,
+}
+
+impl LimitOrderBook {
+    pub const SPACE: usize = LIMIT_FILLS_OFFSET as usize
+        + LIMIT_FILLS_LEN as usize
+        + 16 // fills_nonce
+        + (1 + 1); // pending_slot
+
+    /// Takes the next free slot for `owner` until its placement lands, or returns `None` once
+    /// the book is full
+    pub fn reserve_slot(&mut self, owner: Pubkey) -> Option<u8> {
+        if self.order_count as usize >= MAX_LIMIT_ORDERS {
+            return None;
+        }
+        let slot = self.order_count;
+        self.owners[slot as usize] = owner;
+        self.order_count += 1;
+        self.pending_slot = Some(slot);
+        Some(slot)
+    }
+
+    /// Ends the pending placement; a failed one gives its slot back. Placements are serialized,
+    /// so the pending slot is always the last one taken.
+    pub fn release_pending_slot(&mut self, placed: bool) {
+        if let Some(slot) = self.pending_slot.take() {
+            if !placed {
+                self.owners[slot as usize] = Pubkey::default();
+                self.order_count = slot;
+            }
+        }
+    }
+}
+
+/// Private-window orders collected during one epoch and cleared together at a uniform price
+#[account]
+pub struct OrderBatch {
+    pub bump: u8,
+    pub market: Pubkey,
+    pub epoch: u64,
+    pub opened_ts: i64,
+    pub order_count: u32,
+    pub filled_count: u32,
+    pub cleared: bool,
+    /// Encrypted batch totals: [yes_usdc, no_usdc] as 32-byte ciphertexts
+    pub totals_state: [[u8; 32]; BATCH_ENCRYPTED_FIELDS],
+    pub totals_nonce: u128,
+    /// Encrypted clearing prices: [yes_price, no_price], set when the batch clears
+    pub prices_state: [[u8; 32]; BATCH_ENCRYPTED_FIELDS],
+    pub prices_nonce: u128,
+    /// Set while a clear is queued; orders whose submission lands after this are left out
+    pub clearing: bool,
+}
+
+impl OrderBatch {
+    pub const SPACE: usize = ORDER_BATCH_PRICES_OFFSET as usize + ORDER_BATCH_STATE_LEN as usize + 16 + 1;
+}
+
+/// One trader's encrypted order in an `OrderBatch`
+#[account]
+pub struct BatchOrder {
+    pub bump: u8,
+    pub order_batch: Pubkey,
+    pub user: Pubkey,
+    pub filled: bool,
+    /// Encrypted order: [usdc_amount, is_buy_yes] as 32-byte ciphertexts
+    pub order_state: [[u8; 32]; TRADE_INPUT_ENCRYPTED_FIELDS],
+    pub nonce: u128,
+    /// Set while the submission awaits its callback
+    pub pending: bool,
+}
+
+impl BatchOrder {
+    pub const SPACE: usize = BATCH_ORDER_STATE_OFFSET as usize
+        + BATCH_ORDER_STATE_LEN as usize
+        + 16 // nonce
+        + 1; // pending
+}
+
+/// Hash of a signal provider's off-chain research attached to one of their trades.
+/// Encrypted to the MXE until the market resolves; the sponsor can view it before then.
+#[account]
+pub struct ResearchCommitment {
+    pub bump: u8,
+    pub user_position: Pubkey,
+    pub market: Pubkey,
+    pub user: Pubkey,
+    /// `UserPosition.trade_count` of the trade the research belongs to
+    pub trade_id: u64,
+    /// Time that trade was applied to the position
+    pub committed_ts: i64,
+    /// Encrypted content hash: [hash_hi, hash_lo] as 32-byte ciphertexts
+    pub note_state: [[u8; 32]; RESEARCH_NOTE_ENCRYPTED_FIELDS],
+    pub nonce: u128,
+    /// Plaintext content hash, set once revealed after resolution
+    pub revealed_hash: Option<[u8; 32]>,
+}
+
+impl ResearchCommitment {
+    pub const SPACE: usize = RESEARCH_NOTE_STATE_OFFSET as usize
+        + RESEARCH_NOTE_STATE_LEN as usize
+        + 16 // nonce
+        + (1 + 32); // revealed_hash
+}
+
+/// Per-wallet track record built from scored positions in resolved markets
+#[account]
+pub struct TraderProfile {
+    pub bump: u8,
+    pub wallet: Pubkey,
+    pub positions_scored: u64,
+    pub total_staked_usdc: u64,
+    pub total_payout_usdc: u64,
+    pub realized_pnl: i64,
+    /// Sum of brier (thousandths) * staked_usdc over scored positions
+    pub brier_weighted_sum: u128,
+    /// Sum of staked_usdc over scored positions
+    pub brier_weight: u64,
+}
+
+impl TraderProfile {
+    pub const SPACE: usize = 8 + 1 + 32 + 8 + 8 + 8 + 8 + 16 + 8;
+
+    /// Stake-weighted Brier score in thousandths; lower is better
+    pub fn average_brier(&self) -> u64 {
+        if self.brier_weight == 0 {
+            return 0;
+        }
+        (self.brier_weighted_sum / self.brier_weight as u128) as u64
+    }
+
+    /// Adds one scored position to the track record, or returns `None` if a total would overflow
+    pub fn record_score(&mut self, staked_usdc: u64, payout_usdc: u64, brier: u64) -> Option<()> {
+        let pnl = payout_usdc as i64 - staked_usdc as i64;
+        self.positions_scored += 1;
+        self.total_staked_usdc = self.total_staked_usdc.checked_add(staked_usdc)?;
+        self.total_payout_usdc = self.total_payout_usdc.checked_add(payout_usdc)?;
+        self.realized_pnl = self.realized_pnl.checked_add(pnl)?;
+        self.brier_weighted_sum += brier as u128 * staked_usdc as u128;
+        self.brier_weight = self.brier_weight.checked_add(staked_usdc)?;
+        Some(())
+    }
+}
+
+/// Links several binary markets of one sponsor into a "first N" group:
+/// at most `max_yes` of them can resolve YES, after which the rest resolve NO.
+#[account]
+pub struct MarketGroup {
+    pub bump: u8,
+    pub sponsor: Pubkey,  // Reference to Sponsor account
+    pub authority: Pubkey,
+    pub name: String,  // Max 100 chars
+    pub max_yes: u8,
+    /// USDC deposited into the group's `group_vault` token account; the vault balance must cover
+    /// `max_yes` markets at the largest liquidity cap
+    pub collateral_usdc: u64,
+    pub max_liquidity_cap: u64,
+    pub markets: Vec<Pubkey>,  // Max MAX_GROUP_MARKETS entries
+    pub resolved_yes: u8,
+    pub resolved_count: u8,
+    /// Set once `max_yes` markets resolved YES; remaining markets can then only resolve NO
+    pub completed: bool,
+}
+
+impl MarketGroup {
+    pub const SPACE: usize = 8 + 1 + 32 + 32 + (4 + 100) + 1 + 8 + 8 + (4 + 32 * MAX_GROUP_MARKETS) + 1 + 1 + 1;
+
+    /// Counts a member market's outcome and returns whether it completed the group, or `None`
+    /// for a YES once `max_yes` markets have already resolved YES.
+    pub fn record_outcome(&mut self, outcome: u8) -> Option<bool> {
+        if outcome == OUTCOME_YES {
+            if self.completed {
+                return None;
+            }
+            self.resolved_yes += 1;
+        }
+        self.resolved_count += 1;
+
+        if !self.completed && self.resolved_yes >= self.max_yes {
+            self.completed = true;
+            return Some(true);
+        }
+        Some(false)
+    }
+}
+
+/// Pending optimistic resolution of a market. Holds the proposer's bond and,
+/// once disputed, the disputer's counter-bond.
+#[account]
+pub struct ResolutionProposal {
+    pub bump: u8,
+    pub market: Pubkey,
+    pub proposer: Pubkey,
+    pub outcome: u8,
+    pub value: u64,  // Scalar markets only
+    pub bond: u64,
+    pub proposed_ts: i64,
+    pub dispute_deadline: i64,
+    pub disputer: Option<Pubkey>,
+}
+
+impl ResolutionProposal {
+    pub const SPACE: usize = 8 + 1 + 32 + 32 + 1 + 8 + 8 + 8 + 8 + (1 + 32);
+}
+
+#[account]
+pub struct CategoricalState {
+    pub bump: u8,
+    pub market: Pubkey,
+    /// Encrypted categorical state: [pools[0..MAX_OUTCOMES], total_trades] as 32-byte ciphertexts
+    pub state: [[u8; 32]; CATEGORICAL_STATE_ENCRYPTED_FIELDS],
+    pub nonce: u128,
+}
+
+#[account]
+pub struct CategoricalPosition {
+    pub bump: u8,
+    pub user: Pubkey,
+    pub market: Pubkey,
+    /// Encrypted categorical position: [tokens[0..MAX_OUTCOMES], net_usdc] as 32-byte ciphertexts
+    pub position_state: [[u8; 32]; CATEGORICAL_POSITION_ENCRYPTED_FIELDS],
+    pub nonce: u128,
+    /// USDC paid back to the trader from the market vault once an invalid market's refund has been computed
+    pub refund_usdc: Option<u64>,
+    /// A refund computation is queued; further claims are rejected until its callback lands
+    pub refund_pending: bool,
+    pub failed_updates: u32,
+    pub last_failure_ts: i64,
+    /// Bumped on every write to `position_state`; callbacks drop output computed from an older version
+    pub state_version: u64,
+}
+
+impl CategoricalPosition {
+    pub fn record_failure(&mut self, now: i64) {
+        self.failed_updates += 1;
+        self.last_failure_ts = now;
+    }
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct CloseRecord {
     pub timestamp: i64,
@@ -4,8 +4,6 @@
  * 1. Initialize every Arcium computation definition PDA on the target cluster.
  *    - Call `init_initialize_market_comp_def`.
  *    - Call `init_initialize_user_position_comp_def`.
- *    - Call `init_process_private_trade_comp_def`.
- *    - Call `init_update_user_position_comp_def`.
  *    - Call `init_close_position_comp_def`.
  *    - Call `init_reveal_market_state_comp_def`.
  *    - Call `init_reveal_user_position_comp_def`.
@@ -202,8 +200,6 @@ async function createPlannerAndExecutor(
 const COMP_DEF_CIRCUITS: ArciumCircuitName[] = [
 	'initialize_market',
 	'initialize_user_position',
-	'process_private_trade',
-	'update_user_position',
 	'close_position',
 	'reveal_market_state',
 	'reveal_user_position',
@@ -264,10 +260,6 @@ async function initAndFinalizeCompDefs(args: {
 						return 'makeInitInitializeMarketCompDefIxAsync';
 					case 'initialize_user_position':
 						return 'makeInitInitializeUserPositionCompDefIxAsync';
-					case 'process_private_trade':
-						return 'makeInitProcessPrivateTradeCompDefIxAsync';
-					case 'update_user_position':
-						return 'makeInitUpdateUserPositionCompDefIxAsync';
 					case 'close_position':
 						return 'makeInitClosePositionCompDefIxAsync';
 					case 'reveal_market_state':